@@ -0,0 +1,105 @@
+//! Compares day 6's bitmask answer sets with the `HashSet<char>` per person they replaced, on a
+//! survey much larger than the puzzle input. Run with `cargo bench --bench answer_sets`.
+
+extern crate advent_of_code_2020;
+
+use advent_of_code_2020::day6;
+use std::collections::HashSet;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const GROUPS: usize = 100_000;
+const RUNS: usize = 11;
+
+/// A survey of `GROUPS` groups of one to six people, from a fixed pseudo-random sequence.
+fn survey() -> String {
+    let mut state: u64 = 0x2020_0006;
+    let mut next = |n: u64| {
+        state = state
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1);
+        (state >> 33) % n
+    };
+    let mut out = String::new();
+    for _ in 0..GROUPS {
+        for _ in 0..=next(6) {
+            let mut answered = false;
+            for c in b'a'..=b'z' {
+                if next(3) == 0 {
+                    out.push(c as char);
+                    answered = true;
+                }
+            }
+            if !answered {
+                out.push('q');
+            }
+            out.push('\n');
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// The previous implementation: a `HashSet` per person, intersected through an `Option`.
+fn hash_sets(input: &str) -> (usize, usize) {
+    let groups: Vec<Vec<Vec<char>>> = input
+        .split("\n\n")
+        .map(|g| g.lines().map(|l| l.chars().collect()).collect())
+        .collect();
+    let any = groups
+        .iter()
+        .map(|group| {
+            group
+                .iter()
+                .flat_map(|v| v.iter().copied().collect::<HashSet<char>>())
+                .collect::<HashSet<char>>()
+                .len()
+        })
+        .sum();
+    let all = groups
+        .iter()
+        .map(|group| {
+            group
+                .iter()
+                .map(|v| v.iter().copied().collect::<HashSet<char>>())
+                .fold(Option::None, |l: Option<HashSet<char>>, g| match l {
+                    None => Some(g),
+                    Some(l) => Some(l.intersection(&g).copied().collect()),
+                })
+                .unwrap_or_default()
+                .len()
+        })
+        .sum();
+    (any, all)
+}
+
+fn answer_sets(input: &str) -> (usize, usize) {
+    let groups = day6::parse(input).unwrap();
+    (day6::part1(&groups), day6::part2(&groups))
+}
+
+fn median(input: &str, f: fn(&str) -> (usize, usize)) -> (Duration, (usize, usize)) {
+    let mut times = vec![];
+    let mut answers = (0, 0);
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        answers = black_box(f(black_box(input)));
+        times.push(start.elapsed());
+    }
+    times.sort();
+    (times[RUNS / 2], answers)
+}
+
+fn main() {
+    let input = survey();
+    let (before, expected) = median(&input, hash_sets);
+    let (after, actual) = median(&input, answer_sets);
+    assert_eq!(actual, expected, "the two implementations disagree");
+    println!("{} groups, {} bytes", GROUPS, input.len());
+    println!("hash sets:   {:>10.3?}", before);
+    println!("answer sets: {:>10.3?}", after);
+    println!(
+        "speedup:     {:>10.1}x",
+        before.as_secs_f64() / after.as_secs_f64()
+    );
+}
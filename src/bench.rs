@@ -0,0 +1,244 @@
+use crate::error::{ConfigError, ParseError};
+use crate::solution::Solution;
+use serde_json::{json, Value};
+use std::fmt;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+/// A timed step of solving a day.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
+pub enum Phase {
+    Parse,
+    Part1,
+    Part2,
+}
+
+impl Phase {
+    fn from_name(name: &str) -> Option<Phase> {
+        match name {
+            "parse" => Some(Phase::Parse),
+            "part1" => Some(Phase::Part1),
+            "part2" => Some(Phase::Part2),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Phase::Parse => write!(f, "parse"),
+            Phase::Part1 => write!(f, "part1"),
+            Phase::Part2 => write!(f, "part2"),
+        }
+    }
+}
+
+/// The median time of one phase of one day.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Measurement {
+    pub day: u8,
+    pub phase: Phase,
+    pub median: Duration,
+}
+
+fn median<F: FnMut()>(runs: usize, mut f: F) -> Duration {
+    let mut times: Vec<Duration> = (0..runs.max(1))
+        .map(|_| {
+            let start = Instant::now();
+            f();
+            start.elapsed()
+        })
+        .collect();
+    times.sort_unstable();
+    times[times.len() / 2]
+}
+
+/// Times parsing `input` and solving each implemented part of `solution`, `runs` times each.
+pub fn bench_day(
+    solution: &dyn Solution,
+    input: &str,
+    runs: usize,
+) -> Result<Vec<Measurement>, ParseError> {
+    let day = solution.day();
+    let parsed = solution.parse(input)?;
+    let mut measurements = vec![Measurement {
+        day,
+        phase: Phase::Parse,
+        median: median(runs, || {
+            black_box(solution.parse(black_box(input)).ok());
+        }),
+    }];
+    for (part, phase) in [(1, Phase::Part1), (2, Phase::Part2)].iter() {
+        if solution.has_part(*part) {
+            measurements.push(Measurement {
+                day,
+                phase: *phase,
+                median: median(runs, || {
+                    black_box(solution.part(*part, black_box(&parsed)));
+                }),
+            });
+        }
+    }
+    Ok(measurements)
+}
+
+/// Writes `measurements` as a JSON report that can later be read back as a baseline.
+pub fn to_json(measurements: &[Measurement]) -> Value {
+    Value::Array(
+        measurements
+            .iter()
+            .map(|m| {
+                let nanos = m.median.as_nanos() as u64;
+                json!({"day": m.day, "phase": m.phase.to_string(), "nanos": nanos})
+            })
+            .collect(),
+    )
+}
+
+/// Reads a report written by [`to_json`].
+pub fn from_json(text: &str) -> Result<Vec<Measurement>, ConfigError> {
+    let invalid = |message: String| ConfigError::new("baseline", message);
+    let value: Value = serde_json::from_str(text).map_err(|e| invalid(e.to_string()))?;
+    let entries = value
+        .as_array()
+        .ok_or_else(|| invalid(String::from("expected an array of measurements")))?;
+    entries
+        .iter()
+        .map(|entry| {
+            let day = entry["day"].as_u64().filter(|d| *d <= 25);
+            let phase = entry["phase"].as_str().and_then(Phase::from_name);
+            let nanos = entry["nanos"].as_u64();
+            match (day, phase, nanos) {
+                (Some(day), Some(phase), Some(nanos)) => Ok(Measurement {
+                    day: day as u8,
+                    phase,
+                    median: Duration::from_nanos(nanos),
+                }),
+                _ => Err(invalid(format!("malformed measurement `{}`", entry))),
+            }
+        })
+        .collect()
+}
+
+/// A measurement alongside the baseline's timing of the same phase.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Comparison {
+    pub current: Measurement,
+    pub baseline: Option<Duration>,
+    /// Whether `current` is slower than the baseline by more than the allowed threshold.
+    pub regressed: bool,
+}
+
+impl Comparison {
+    /// How much slower (positive) or faster (negative) than the baseline, in percent.
+    pub fn change(&self) -> Option<f64> {
+        self.baseline
+            .filter(|b| !b.is_zero())
+            .map(|b| (self.current.median.as_secs_f64() / b.as_secs_f64() - 1.0) * 100.0)
+    }
+}
+
+/// Compares `current` against `baseline`, flagging phases more than `threshold` percent slower.
+pub fn compare(
+    current: &[Measurement],
+    baseline: &[Measurement],
+    threshold: f64,
+) -> Vec<Comparison> {
+    current
+        .iter()
+        .map(|&m| {
+            let baseline = baseline
+                .iter()
+                .find(|b| b.day == m.day && b.phase == m.phase)
+                .map(|b| b.median);
+            let mut comparison = Comparison {
+                current: m,
+                baseline,
+                regressed: false,
+            };
+            comparison.regressed = comparison.change().is_some_and(|c| c > threshold);
+            comparison
+        })
+        .collect()
+}
+
+/// Renders `comparisons` as a table of timings and their change from the baseline.
+pub fn table(comparisons: &[Comparison]) -> String {
+    let mut out = String::from("day phase        time     baseline   change\n");
+    for c in comparisons {
+        let baseline = c
+            .baseline
+            .map_or(String::from("-"), |b| format!("{:.2?}", b));
+        let change = match c.change() {
+            Some(change) if c.regressed => format!("{:+.1}% REGRESSED", change),
+            Some(change) => format!("{:+.1}%", change),
+            None => String::from("-"),
+        };
+        out.push_str(&format!(
+            "{:>3} {:<5} {:>12} {:>12} {:>8}\n",
+            c.current.day,
+            c.current.phase,
+            format!("{:.2?}", c.current.median),
+            baseline,
+            change
+        ));
+    }
+    let regressed = comparisons.iter().filter(|c| c.regressed).count();
+    out.push_str(&format!("{} regressed", regressed));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solution::solution;
+
+    fn measurement(day: u8, phase: Phase, micros: u64) -> Measurement {
+        Measurement {
+            day,
+            phase,
+            median: Duration::from_micros(micros),
+        }
+    }
+
+    #[test]
+    fn test_bench_day() {
+        let day1 = solution(1).unwrap();
+        let measurements = bench_day(&*day1, "1721\n979\n366\n299\n675\n1456", 3).unwrap();
+        let phases: Vec<Phase> = measurements.iter().map(|m| m.phase).collect();
+        assert_eq!(phases, vec![Phase::Parse, Phase::Part1, Phase::Part2]);
+        assert!(bench_day(&*day1, "x", 3).is_err());
+    }
+
+    #[test]
+    fn test_report_round_trip() {
+        let measurements = vec![
+            measurement(1, Phase::Parse, 10),
+            measurement(1, Phase::Part2, 2000),
+        ];
+        let report = to_json(&measurements).to_string();
+        assert_eq!(from_json(&report), Ok(measurements));
+        assert!(from_json(r#"[{"day": 1, "phase": "part3", "nanos": 1}]"#).is_err());
+        assert!(from_json(r#"{"day": 1}"#).is_err());
+    }
+
+    #[test]
+    fn test_compare() {
+        let baseline = vec![
+            measurement(1, Phase::Parse, 100),
+            measurement(1, Phase::Part1, 100),
+        ];
+        let current = vec![
+            measurement(1, Phase::Parse, 105),
+            measurement(1, Phase::Part1, 150),
+            measurement(1, Phase::Part2, 100),
+        ];
+        let comparisons = compare(&current, &baseline, 10.0);
+        let regressed: Vec<bool> = comparisons.iter().map(|c| c.regressed).collect();
+        assert_eq!(regressed, vec![false, true, false]);
+        assert_eq!(comparisons[2].baseline, None);
+        assert!((comparisons[1].change().unwrap() - 50.0).abs() < 1e-9);
+        assert!(table(&comparisons).ends_with("1 regressed"));
+    }
+}
@@ -1,51 +1,264 @@
-fn sums_to<'a>(
-    sum: &'_ usize,
-    curr: &'a usize,
-    rest: &'a [usize],
-) -> Option<(&'a usize, &'a usize)> {
-    if rest.len() == 0 {
-        return Option::None;
-    }
-    for re in rest {
-        if re + curr == *sum {
-            return Option::Some((curr, re));
+use crate::error::{parse_lines, parse_number, ParseError};
+use std::collections::BTreeMap;
+
+/// The sum the puzzle looks for.
+pub const TARGET: usize = 2020;
+
+/// Searches `sorted` for `k` more entries summing to `target`, calling `found` with each distinct
+/// combination (appended to `chosen`) until it returns `true`. Returns whether the search was
+/// stopped.
+fn k_sum(
+    sorted: &[usize],
+    target: usize,
+    k: usize,
+    chosen: &mut Vec<usize>,
+    found: &mut dyn FnMut(&[usize]) -> bool,
+) -> bool {
+    match k {
+        0 => target == 0 && found(chosen),
+        1 => {
+            if sorted.binary_search(&target).is_err() {
+                return false;
+            }
+            chosen.push(target);
+            let stop = found(chosen);
+            chosen.pop();
+            stop
+        }
+        2 => {
+            let (mut i, mut j) = (0, sorted.len());
+            while i + 1 < j {
+                let (a, b) = (sorted[i], sorted[j - 1]);
+                let sum = a.checked_add(b);
+                match sum.map_or(std::cmp::Ordering::Greater, |s| s.cmp(&target)) {
+                    std::cmp::Ordering::Less => i += 1,
+                    std::cmp::Ordering::Greater => j -= 1,
+                    std::cmp::Ordering::Equal => {
+                        chosen.extend_from_slice(&[a, b]);
+                        let stop = found(chosen);
+                        chosen.truncate(chosen.len() - 2);
+                        if stop {
+                            return true;
+                        }
+                        while i + 1 < j && sorted[i] == a {
+                            i += 1;
+                        }
+                        while i + 1 < j && sorted[j - 1] == b {
+                            j -= 1;
+                        }
+                    }
+                }
+            }
+            false
         }
+        _ => {
+            for (i, &v) in sorted.iter().enumerate() {
+                if v > target {
+                    break;
+                }
+                if i > 0 && sorted[i - 1] == v {
+                    continue;
+                }
+                chosen.push(v);
+                let stop = k_sum(&sorted[i + 1..], target - v, k - 1, chosen, found);
+                chosen.pop();
+                if stop {
+                    return true;
+                }
+            }
+            false
+        }
+    }
+}
+
+fn sorted(values: &[usize]) -> Vec<usize> {
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable();
+    sorted
+}
+
+/// Every distinct combination of `k` entries summing to `target`, each in ascending order.
+/// Combinations are distinct by value, so an entry may only repeat as often as it appears in
+/// `values`.
+pub fn find_k_sum(values: &[usize], target: usize, k: usize) -> Vec<Vec<usize>> {
+    let mut combinations = vec![];
+    k_sum(&sorted(values), target, k, &mut vec![], &mut |c| {
+        combinations.push(c.to_vec());
+        false
+    });
+    combinations
+}
+
+/// The first combination [`find_k_sum`] would return, without searching for the rest.
+pub fn find_first_k_sum(values: &[usize], target: usize, k: usize) -> Option<Vec<usize>> {
+    let mut first = None;
+    k_sum(&sorted(values), target, k, &mut vec![], &mut |c| {
+        first = Some(c.to_vec());
+        true
+    });
+    first
+}
+
+/// One combination of entries summing to the target.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Match {
+    /// The entries, in ascending order.
+    pub entries: Vec<usize>,
+    /// The line (0-based) of each entry in the report; repeated values use successive lines.
+    pub indices: Vec<usize>,
+    /// The product of the entries, or `None` if it overflows.
+    pub product: Option<usize>,
+}
+
+/// Every combination of `k` entries in an expense report summing to `target`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Analysis {
+    pub target: usize,
+    pub k: usize,
+    pub matches: Vec<Match>,
+    /// Entries that appear on more than one line, with each of their lines; a match using one of
+    /// them could have used any of its lines.
+    pub duplicates: BTreeMap<usize, Vec<usize>>,
+}
+
+impl Analysis {
+    /// Whether more than one distinct combination sums to the target.
+    pub fn is_ambiguous(&self) -> bool {
+        self.matches.len() > 1
+    }
+
+    /// Whether the matches disagree on the product, i.e. the puzzle has no single answer.
+    pub fn has_conflicting_products(&self) -> bool {
+        self.matches
+            .windows(2)
+            .any(|w| w[0].product != w[1].product)
     }
-    sums_to(sum, &rest[0], &rest[1..])
 }
 
-fn tri_sums_to<'a>(
-    sum: &'_ usize,
-    x: &'a usize,
-    rest: &'a [usize],
-) -> Option<(&'a usize, &'a usize, &'a usize)> {
-    if rest.len() == 0 {
-        return Option::None;
+/// Finds every distinct combination of `k` entries summing to `target`, with where each entry
+/// appears in `values` and which entries are duplicated.
+pub fn analyze(values: &[usize], target: usize, k: usize) -> Analysis {
+    let mut lines: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for (i, &v) in values.iter().enumerate() {
+        lines.entry(v).or_default().push(i);
     }
-    if x < sum {
-        let sub_sum = *sum - x;
-        for i in 0..(rest.len() - 1) {
-            if let Some((a, b)) = sums_to(&sub_sum, &rest[i], &rest[i + 1..]) {
-                return Option::Some((x, a, b));
+    let matches = find_k_sum(values, target, k)
+        .into_iter()
+        .map(|entries| {
+            let mut used: BTreeMap<usize, usize> = BTreeMap::new();
+            let indices = entries
+                .iter()
+                .map(|v| {
+                    let n = used.entry(*v).or_insert(0);
+                    *n += 1;
+                    lines[v][*n - 1]
+                })
+                .collect();
+            let product = entries.iter().try_fold(1usize, |p, &v| p.checked_mul(v));
+            Match {
+                entries,
+                indices,
+                product,
             }
-        }
+        })
+        .collect();
+    lines.retain(|_, l| l.len() > 1);
+    Analysis {
+        target,
+        k,
+        matches,
+        duplicates: lines,
     }
-    tri_sums_to(sum, &rest[0], &rest[1..])
 }
 
+fn product_of_k_sum(input: &[usize], k: usize) -> usize {
+    find_first_k_sum(input, TARGET, k).map_or(0, |c| c.iter().product())
+}
+
+/// Parses the expense report, one entry per line.
 #[aoc_generator(day1)]
-fn input_to_vec(input: &str) -> Vec<usize> {
-    input.lines().map(|i| i.parse().unwrap()).collect()
+pub fn parse(input: &str) -> Result<Vec<usize>, ParseError> {
+    parse_lines(input, |l| parse_number(1, l, l.trim()))
 }
 
+/// The product of the two entries that sum to 2020, or 0 when there are none.
 #[aoc(day1, part1)]
-fn day1_1(input: &Vec<usize>) -> usize {
-    let (v1, v2) = sums_to(&2020, &input[0], &input[1..]).unwrap();
-    v1 * v2
+pub fn part1(input: &[usize]) -> usize {
+    product_of_k_sum(input, 2)
 }
 
+/// The product of the three entries that sum to 2020, or 0 when there are none.
 #[aoc(day1, part2)]
-fn day1_2(input: &Vec<usize>) -> usize {
-    let (v1, v2, v3) = tri_sums_to(&2020, &input[0], &input[1..]).unwrap();
-    v1 * v2 * v3
+pub fn part2(input: &[usize]) -> usize {
+    product_of_k_sum(input, 3)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_INPUT: &[usize] = &[1721, 979, 366, 299, 675, 1456];
+
+    #[test]
+    fn test_parts() {
+        assert_eq!(part1(TEST_INPUT), 514579);
+        assert_eq!(part2(TEST_INPUT), 241861950);
+        assert_eq!(part1(&[2020]), 0);
+    }
+
+    #[test]
+    fn test_find_k_sum() {
+        assert_eq!(find_k_sum(TEST_INPUT, 2020, 2), vec![vec![299, 1721]]);
+        assert_eq!(find_k_sum(TEST_INPUT, 2020, 3), vec![vec![366, 675, 979]]);
+        assert_eq!(find_k_sum(&[1, 2, 3, 4, 5, 5], 10, 2), vec![vec![5, 5]]);
+        assert_eq!(
+            find_k_sum(&[1, 2, 3, 4, 5, 5], 10, 3),
+            vec![vec![1, 4, 5], vec![2, 3, 5]]
+        );
+        assert_eq!(find_k_sum(&[1, 2, 3, 4], 10, 4), vec![vec![1, 2, 3, 4]]);
+        assert_eq!(find_k_sum(&[5], 10, 2), Vec::<Vec<usize>>::new());
+        assert_eq!(find_k_sum(&[5, 10], 10, 1), vec![vec![10]]);
+        assert_eq!(find_k_sum(&[5], 0, 0), vec![Vec::<usize>::new()]);
+    }
+
+    #[test]
+    fn test_analyze() {
+        let analysis = analyze(TEST_INPUT, TARGET, 2);
+        assert_eq!(
+            analysis.matches,
+            vec![Match {
+                entries: vec![299, 1721],
+                indices: vec![3, 0],
+                product: Some(514579),
+            }]
+        );
+        assert!(!analysis.is_ambiguous());
+        assert!(analysis.duplicates.is_empty());
+
+        let analysis = analyze(&[5, 1, 9, 5, 3, 7], 10, 2);
+        let entries: Vec<_> = analysis.matches.iter().map(|m| &m.entries).collect();
+        assert_eq!(entries, vec![&vec![1, 9], &vec![3, 7], &vec![5, 5]]);
+        assert_eq!(analysis.matches[2].indices, vec![0, 3]);
+        assert_eq!(analysis.duplicates[&5], vec![0, 3]);
+        assert!(analysis.is_ambiguous());
+        assert!(analysis.has_conflicting_products());
+
+        let analysis = analyze(&[usize::MAX, 0, 1], usize::MAX, 2);
+        assert_eq!(analysis.matches[0].product, Some(0));
+        assert_eq!(analysis.matches.len(), 1);
+    }
+
+    #[test]
+    fn test_find_first_k_sum() {
+        assert_eq!(
+            find_first_k_sum(&[1, 2, 3, 4, 5, 5], 10, 3),
+            Some(vec![1, 4, 5])
+        );
+        assert_eq!(find_first_k_sum(&[1, 2, 3], 100, 3), None);
+        let large: Vec<usize> = (0..100_000).collect();
+        assert_eq!(
+            find_first_k_sum(&large, 199_997, 2),
+            Some(vec![99_998, 99_999])
+        );
+    }
 }
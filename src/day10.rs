@@ -1,20 +1,26 @@
+use crate::error::{missing, parse_lines, parse_number, ParseError};
 use std::collections::HashMap;
 
+/// Parses the adapter ratings, returned sorted with the outlet (0) and the device (max + 3) added.
 #[aoc_generator(day10)]
-fn to_vec(input: &str) -> Vec<usize> {
-    let vec: Vec<usize> = input.lines().map(|i| i.parse().unwrap()).collect();
-    let max = vec.iter().max().unwrap();
+pub fn parse(input: &str) -> Result<Vec<usize>, ParseError> {
+    let vec: Vec<usize> = parse_lines(input, |l| parse_number(10, l, l.trim()))?;
+    let max = vec
+        .iter()
+        .max()
+        .ok_or_else(|| missing(10, input, "adapters"))?;
     let mut new_vec = [&[0], vec.as_slice(), &[*max + 3]].concat();
     new_vec.sort();
-    new_vec
+    Ok(new_vec)
 }
 
+/// The number of 1-jolt differences multiplied by the number of 3-jolt differences.
 #[aoc(day10, part1)]
-fn the_path(input: &Vec<usize>) -> usize {
+pub fn part1(input: &[usize]) -> usize {
     let mut one_count = 0;
     let mut three_count = 0;
     for (i, v) in input[..input.len() - 1].iter().enumerate() {
-        let diff = ((*v) as i32 - (input[i + 1]) as i32).abs() as usize;
+        let diff = ((*v) as i32 - (input[i + 1]) as i32).unsigned_abs() as usize;
         if diff == 1 {
             one_count += 1;
         } else if diff == 3 {
@@ -24,8 +30,9 @@ fn the_path(input: &Vec<usize>) -> usize {
     one_count * three_count
 }
 
+/// The number of distinct adapter arrangements connecting the outlet to the device.
 #[aoc(day10, part2)]
-fn all_combinations(input: &Vec<usize>) -> usize {
+pub fn part2(input: &[usize]) -> usize {
     let mut the_ways = HashMap::new();
     // Only one way to get to 0 or 1
     the_ways.insert(0, 1);
@@ -90,19 +97,19 @@ mod tests {
 
     #[test]
     fn test_differences() {
-        let input = to_vec(TEST_INPUT);
-        assert_eq!(the_path(&input), 35);
+        let input = parse(TEST_INPUT).unwrap();
+        assert_eq!(part1(&input), 35);
 
-        let input = to_vec(MOAR_TEST_INPUT);
-        assert_eq!(the_path(&input), 220);
+        let input = parse(MOAR_TEST_INPUT).unwrap();
+        assert_eq!(part1(&input), 220);
     }
 
     #[test]
     fn test_all_paths() {
-        let input = to_vec(TEST_INPUT);
-        assert_eq!(all_combinations(&input), 8);
+        let input = parse(TEST_INPUT).unwrap();
+        assert_eq!(part2(&input), 8);
 
-        let input = to_vec(MOAR_TEST_INPUT);
-        assert_eq!(all_combinations(&input), 19208);
+        let input = parse(MOAR_TEST_INPUT).unwrap();
+        assert_eq!(part2(&input), 19208);
     }
 }
@@ -1,161 +1,93 @@
+use crate::error::{ParseError, ParseErrorKind};
+use crate::grid::{Grid, DIRECTIONS};
+use std::str::FromStr;
+
+/// What is at a position in the waiting area.
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
-enum State {
+pub enum State {
     Occupied,
     Unoccupied,
     Floor,
 }
 
-impl From<&str> for State {
-    fn from(s: &str) -> Self {
-        match s {
-            "L" => State::Unoccupied,
-            "#" => State::Occupied,
-            "." => State::Floor,
-            _ => unimplemented!(),
-        }
-    }
-}
-
-#[derive(Copy, Clone, Eq, PartialEq, Debug)]
-struct Seat {
-    coordinates: (usize, usize),
-    state: State,
-}
+impl FromStr for State {
+    type Err = ParseError;
 
-struct CoordinateDirection<'a> {
-    coordinates: (usize, usize),
-    direction: (i8, i8),
-    maximum: &'a (usize, usize),
-}
-
-impl Iterator for CoordinateDirection<'_> {
-    type Item = (usize, usize);
-
-    fn next(&mut self) -> Option<(usize, usize)> {
-        if (self.direction.0 < 0 && self.coordinates.0 == 0)
-            || (self.direction.0 > 0 && self.coordinates.0 >= self.maximum.0)
-            || (self.direction.1 < 0 && self.coordinates.1 == 0)
-            || (self.direction.1 > 0 && self.coordinates.1 >= self.maximum.1)
-        {
-            return None;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "L" => Ok(State::Unoccupied),
+            "#" => Ok(State::Occupied),
+            "." => Ok(State::Floor),
+            _ => Err(ParseError::located(
+                11,
+                s,
+                s,
+                ParseErrorKind::UnexpectedToken,
+            )),
         }
-
-        self.coordinates = (
-            (self.coordinates.0 as i8 + self.direction.0) as usize,
-            (self.coordinates.1 as i8 + self.direction.1) as usize,
-        );
-        Some(self.coordinates)
     }
 }
 
-fn all_coordinate_generators<'a>(
-    seat: &'a Seat,
-    maximum: &'a (usize, usize),
-) -> Vec<CoordinateDirection<'a>> {
-    let iterator_generator = |(dx, dy)| CoordinateDirection {
-        coordinates: seat.coordinates.clone(),
-        maximum: &maximum,
-        direction: (dx, dy),
-    };
-    vec![
-        iterator_generator((1, 0)),
-        iterator_generator((1, 1)),
-        iterator_generator((1, -1)),
-        iterator_generator((-1, 0)),
-        iterator_generator((-1, 1)),
-        iterator_generator((-1, -1)),
-        iterator_generator((0, 1)),
-        iterator_generator((0, -1)),
-    ]
+/// How many of the seats next to `(x, y)` are occupied.
+fn occupied_adjacent(arrangement: &Grid<State>, x: usize, y: usize) -> usize {
+    arrangement
+        .neighbours(x, y)
+        .filter(|&p| arrangement[p] == State::Occupied)
+        .count()
 }
 
-fn new_state(seat: &Seat, arrangement: &Vec<Vec<Seat>>) -> Seat {
-    if seat.state == State::Floor {
-        return seat.clone();
-    }
-    let maximum = (arrangement[0].len() - 1, arrangement.len() - 1);
-    let mut visual_iters = all_coordinate_generators(seat, &maximum);
-    let mut occupied_count = 0;
-    for coor_iter in visual_iters.iter_mut() {
-        if let Some((x, y)) = coor_iter.next() {
-            if arrangement[y][x].state == State::Occupied {
-                occupied_count += 1;
+/// How many of the first seats visible from `(x, y)` in each direction are occupied.
+fn occupied_visible(arrangement: &Grid<State>, x: usize, y: usize) -> usize {
+    DIRECTIONS
+        .iter()
+        .filter(|&&(dx, dy)| {
+            let (mut x, mut y) = (x as isize, y as isize);
+            loop {
+                x += dx;
+                y += dy;
+                match arrangement.get(x, y) {
+                    Some(State::Floor) => continue,
+                    Some(State::Occupied) => return true,
+                    _ => return false,
+                }
             }
-        }
-    }
-    let state = if occupied_count >= 4 && seat.state == State::Occupied {
-        State::Unoccupied
-    } else if occupied_count == 0 && seat.state == State::Unoccupied {
-        State::Occupied
-    } else {
-        seat.state.clone()
-    };
-    Seat {
-        coordinates: seat.coordinates.clone(),
-        state,
-    }
+        })
+        .count()
 }
 
-fn new_state_visually(seat: &Seat, arrangement: &Vec<Vec<Seat>>) -> Seat {
-    if seat.state == State::Floor {
-        return seat.clone();
-    }
-    let maximum = (arrangement[0].len() - 1, arrangement.len() - 1);
-    let mut visual_iters = all_coordinate_generators(seat, &maximum);
-    let mut occupied_count = 0;
-    for coor_iter in visual_iters.iter_mut() {
-        if let Some((x, y)) = coor_iter
-            .skip_while(|(x, y)| arrangement[*y][*x].state == State::Floor)
-            .next()
-        {
-            if arrangement[y][x].state == State::Occupied {
-                occupied_count += 1;
-            }
-        }
-    }
-    let state = if occupied_count > 4 && seat.state == State::Occupied {
-        State::Unoccupied
-    } else if occupied_count == 0 && seat.state == State::Unoccupied {
-        State::Occupied
-    } else {
-        seat.state.clone()
-    };
-    Seat {
-        coordinates: seat.coordinates.clone(),
-        state,
+/// The state of `(x, y)` after one round, where people leave a seat when `tolerance` or more
+/// of the seats they consider are occupied.
+fn new_state(
+    arrangement: &Grid<State>,
+    x: usize,
+    y: usize,
+    occupied: fn(&Grid<State>, usize, usize) -> usize,
+    tolerance: usize,
+) -> State {
+    match arrangement[(x, y)] {
+        State::Floor => State::Floor,
+        State::Occupied if occupied(arrangement, x, y) >= tolerance => State::Unoccupied,
+        State::Unoccupied if occupied(arrangement, x, y) == 0 => State::Occupied,
+        state => state,
     }
 }
 
+/// Parses the seat layout into a grid.
 #[aoc_generator(day11)]
-fn to_vec(input: &str) -> Vec<Vec<Seat>> {
-    let mut seats = vec![];
-    for (ys, vals) in input.lines().enumerate() {
-        let mut row = vec![];
-        for (xs, state) in vals.split("").filter(|s| !(*s).is_empty()).enumerate() {
-            let seat = Seat {
-                state: state.into(),
-                coordinates: (xs, ys),
-            };
-            row.push(seat);
-        }
-        seats.push(row);
-    }
-    seats
+pub fn parse(input: &str) -> Result<Grid<State>, ParseError> {
+    Grid::parse(11, input)
 }
 
 fn reach_stability_count(
-    input: &Vec<Vec<Seat>>,
-    state_check: &dyn Fn(&Seat, &Vec<Vec<Seat>>) -> Seat,
+    input: &Grid<State>,
+    occupied: fn(&Grid<State>, usize, usize) -> usize,
+    tolerance: usize,
 ) -> usize {
     let mut old_arrangement = input.clone();
     loop {
-        let mut new_arrangement = vec![];
-        for row in old_arrangement.iter() {
-            let mut new_row = vec![];
-            for seat in row.iter() {
-                new_row.push(state_check(&seat, &old_arrangement));
-            }
-            new_arrangement.push(new_row);
+        let mut new_arrangement = old_arrangement.clone();
+        for ((x, y), _) in old_arrangement.iter() {
+            new_arrangement[(x, y)] = new_state(&old_arrangement, x, y, occupied, tolerance);
         }
         if new_arrangement == old_arrangement {
             break;
@@ -164,19 +96,20 @@ fn reach_stability_count(
     }
     old_arrangement
         .iter()
-        .flat_map(|v| v)
-        .filter(|s| (*s).state == State::Occupied)
+        .filter(|&(_, s)| *s == State::Occupied)
         .count()
 }
 
+/// How many seats end up occupied when people only look at adjacent seats.
 #[aoc(day11, part1)]
-fn occupied_seats_in_stability(input: &Vec<Vec<Seat>>) -> usize {
-    reach_stability_count(input, &new_state)
+pub fn part1(input: &Grid<State>) -> usize {
+    reach_stability_count(input, occupied_adjacent, 4)
 }
 
+/// How many seats end up occupied when people look at the first seat visible in each direction.
 #[aoc(day11, part2)]
-fn occupied_seats_in_visual_stability(input: &Vec<Vec<Seat>>) -> usize {
-    reach_stability_count(input, &new_state_visually)
+pub fn part2(input: &Grid<State>) -> usize {
+    reach_stability_count(input, occupied_visible, 5)
 }
 
 #[cfg(test)]
@@ -196,13 +129,13 @@ mod tests {
 
     #[test]
     fn test_stability_count() {
-        let input = to_vec(TEST_INPUT);
-        assert_eq!(occupied_seats_in_stability(&input), 37);
+        let input = parse(TEST_INPUT).unwrap();
+        assert_eq!(part1(&input), 37);
     }
 
     #[test]
     fn test_visual_stability_count() {
-        let input = to_vec(TEST_INPUT);
-        assert_eq!(occupied_seats_in_visual_stability(&input), 26);
+        let input = parse(TEST_INPUT).unwrap();
+        assert_eq!(part2(&input), 26);
     }
 }
@@ -1,5 +1,9 @@
+use crate::error::{missing, parse_lines, parse_number, ParseError, ParseErrorKind};
+use std::str::FromStr;
+
+/// A navigation instruction and its value (a distance, or degrees for turns).
 #[derive(Debug)]
-enum Movement {
+pub enum Movement {
     North(i32),
     South(i32),
     East(i32),
@@ -9,25 +13,39 @@ enum Movement {
     Forward(i32),
 }
 
-impl From<&str> for Movement {
-    fn from(s: &str) -> Self {
-        let v = String::from(&s[..1]);
-        let val: i32 = (&s[1..]).parse().unwrap();
-        match v.as_str() {
-            "N" => Movement::North(val),
-            "S" => Movement::South(val),
-            "E" => Movement::East(val),
-            "W" => Movement::West(val),
-            "L" => Movement::Left(val),
-            "R" => Movement::Right(val),
-            "F" => Movement::Forward(val),
-            _ => unimplemented!(),
+impl FromStr for Movement {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let v = s.chars().next().ok_or_else(|| missing(12, s, "movement"))?;
+        let val: i32 = parse_number(12, s, s[v.len_utf8()..].trim_end())?;
+        match v {
+            'N' => Ok(Movement::North(val)),
+            'S' => Ok(Movement::South(val)),
+            'E' => Ok(Movement::East(val)),
+            'W' => Ok(Movement::West(val)),
+            'L' | 'R' if ![90, 180, 270].contains(&val) => Err(ParseError::located(
+                12,
+                s,
+                &s[1..],
+                ParseErrorKind::UnexpectedToken,
+            )),
+            'L' => Ok(Movement::Left(val)),
+            'R' => Ok(Movement::Right(val)),
+            'F' => Ok(Movement::Forward(val)),
+            _ => Err(ParseError::located(
+                12,
+                s,
+                &s[..v.len_utf8()],
+                ParseErrorKind::UnexpectedToken,
+            )),
         }
     }
 }
 
-#[derive(Debug)]
-enum Facing {
+/// The compass direction the ship is facing.
+#[derive(Copy, Clone, Debug)]
+pub enum Facing {
     N,
     S,
     E,
@@ -35,47 +53,47 @@ enum Facing {
 }
 
 impl Facing {
-    fn new_direction(&self, degrees: &i32) -> Facing {
-        match self {
-            Facing::N => match degrees {
-                90 => Facing::E,
-                180 => Facing::S,
-                270 => Facing::W,
-                _ => unimplemented!(),
-            },
-            Facing::S => match degrees {
-                90 => Facing::W,
-                180 => Facing::N,
-                270 => Facing::E,
-                _ => unimplemented!(),
-            },
-            Facing::E => match degrees {
-                90 => Facing::S,
-                180 => Facing::W,
-                270 => Facing::N,
-                _ => unimplemented!(),
-            },
-            Facing::W => match degrees {
-                90 => Facing::N,
-                180 => Facing::E,
-                270 => Facing::S,
-                _ => unimplemented!(),
-            },
-        }
+    /// The direction after turning clockwise by `degrees`, or `None` unless it is a multiple of
+    /// 90.
+    pub fn new_direction(&self, degrees: &i32) -> Option<Facing> {
+        let turns = match degrees.rem_euclid(360) {
+            0 => 0,
+            90 => 1,
+            180 => 2,
+            270 => 3,
+            _ => return None,
+        };
+        let clockwise = [Facing::N, Facing::E, Facing::S, Facing::W];
+        let from = match self {
+            Facing::N => 0,
+            Facing::E => 1,
+            Facing::S => 2,
+            Facing::W => 3,
+        };
+        Some(clockwise[(from + turns) % 4])
     }
 }
 
+/// The ship's heading and location (east and north are positive) and its waypoint, which is
+/// relative to the ship.
 #[derive(Debug)]
-struct Position {
-    facing: Facing,
-    x: i32,
-    y: i32,
-    waypoint_x: i32,
-    waypoint_y: i32,
+pub struct Position {
+    pub facing: Facing,
+    pub x: i32,
+    pub y: i32,
+    pub waypoint_x: i32,
+    pub waypoint_y: i32,
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Position::new()
+    }
 }
 
 impl Position {
-    fn new() -> Self {
+    /// A ship at the origin facing east, with its waypoint 10 east and 1 north.
+    pub fn new() -> Self {
         Position {
             facing: Facing::E,
             x: 0,
@@ -85,14 +103,18 @@ impl Position {
         }
     }
 
-    fn travel(&mut self, movement: &Movement) {
+    /// Applies `movement` to the ship itself, or returns `None` without moving for a turn that
+    /// is not a multiple of 90 degrees.
+    pub fn travel(&mut self, movement: &Movement) -> Option<()> {
         match movement {
             Movement::North(val) => self.y += *val,
             Movement::South(val) => self.y -= *val,
             Movement::East(val) => self.x += *val,
             Movement::West(val) => self.x -= *val,
-            Movement::Left(val) => self.facing = self.facing.new_direction(&(360 - val)),
-            Movement::Right(val) => self.facing = self.facing.new_direction(val),
+            Movement::Left(val) => {
+                self.facing = self.facing.new_direction(&(360 - val.rem_euclid(360)))?
+            }
+            Movement::Right(val) => self.facing = self.facing.new_direction(val)?,
             Movement::Forward(val) => match self.facing {
                 Facing::N => self.y += *val,
                 Facing::S => self.y -= *val,
@@ -100,67 +122,69 @@ impl Position {
                 Facing::W => self.x -= *val,
             },
         }
+        Some(())
     }
 
-    fn travel_waypoint(&mut self, movement: &Movement) {
+    /// Applies `movement` using the waypoint: directions and turns move the waypoint, forward
+    /// moves the ship towards it. Returns `None` without moving for a turn that is not a
+    /// multiple of 90 degrees.
+    pub fn travel_waypoint(&mut self, movement: &Movement) -> Option<()> {
         match movement {
             Movement::North(val) => self.waypoint_y += *val,
             Movement::South(val) => self.waypoint_y -= *val,
             Movement::East(val) => self.waypoint_x += *val,
             Movement::West(val) => self.waypoint_x -= *val,
-            Movement::Left(val) => self.rotate_waypoint(&(360 - val)),
-            Movement::Right(val) => self.rotate_waypoint(val),
+            Movement::Left(val) => self.rotate_waypoint(&(360 - val.rem_euclid(360)))?,
+            Movement::Right(val) => self.rotate_waypoint(val)?,
             Movement::Forward(val) => {
                 self.x += val * self.waypoint_x;
                 self.y += val * self.waypoint_y;
             }
         }
+        Some(())
     }
 
-    fn rotate_waypoint(&mut self, degrees: &i32) {
-        match degrees {
-            90 => {
-                let new_x = self.waypoint_y;
-                let new_y = -self.waypoint_x;
-                self.waypoint_y = new_y;
-                self.waypoint_x = new_x;
-            }
-            180 => {
-                self.waypoint_x = -self.waypoint_x;
-                self.waypoint_y = -self.waypoint_y;
-            }
-            270 => {
-                let new_x = -self.waypoint_y;
-                let new_y = self.waypoint_x;
-                self.waypoint_y = new_y;
-                self.waypoint_x = new_x;
-            }
-            _ => unimplemented!(),
+    /// Rotates the waypoint clockwise around the ship by `degrees`, or returns `None` without
+    /// moving it unless that is a multiple of 90.
+    pub fn rotate_waypoint(&mut self, degrees: &i32) -> Option<()> {
+        let (x, y) = (self.waypoint_x, self.waypoint_y);
+        let (new_x, new_y) = match degrees.rem_euclid(360) {
+            0 => (x, y),
+            90 => (y, -x),
+            180 => (-x, -y),
+            270 => (-y, x),
+            _ => return None,
         };
+        self.waypoint_x = new_x;
+        self.waypoint_y = new_y;
+        Some(())
     }
 }
 
+/// Parses the navigation instructions, one per line.
 #[aoc_generator(day12)]
-fn to_vec(input: &str) -> Vec<Movement> {
-    input.lines().map(|s| s.into()).collect()
+pub fn parse(input: &str) -> Result<Vec<Movement>, ParseError> {
+    parse_lines(input, str::parse)
 }
 
+/// The Manhattan distance travelled when instructions move the ship.
 #[aoc(day12, part1)]
-fn manhatten_movement(input: &Vec<Movement>) -> usize {
+pub fn part1(input: &[Movement]) -> usize {
     let mut position = Position::new();
     for movement in input {
         position.travel(movement);
     }
-    return (position.x.abs() + position.y.abs()) as usize;
+    (position.x.abs() + position.y.abs()) as usize
 }
 
+/// The Manhattan distance travelled when instructions move the waypoint.
 #[aoc(day12, part2)]
-fn manhatten_waypoint_movement(input: &Vec<Movement>) -> usize {
+pub fn part2(input: &[Movement]) -> usize {
     let mut position = Position::new();
     for movement in input {
         position.travel_waypoint(movement);
     }
-    return (position.x.abs() + position.y.abs()) as usize;
+    (position.x.abs() + position.y.abs()) as usize
 }
 
 #[cfg(test)]
@@ -175,13 +199,35 @@ F11";
 
     #[test]
     fn test_travel() {
-        let input = to_vec(TEST_INPUT);
-        assert_eq!(manhatten_movement(&input), 25);
+        let input = parse(TEST_INPUT).unwrap();
+        assert_eq!(part1(&input), 25);
     }
 
     #[test]
     fn test_waypoint_travel() {
-        let input = to_vec(TEST_INPUT);
-        assert_eq!(manhatten_waypoint_movement(&input), 286);
+        let input = parse(TEST_INPUT).unwrap();
+        assert_eq!(part2(&input), 286);
+    }
+
+    #[test]
+    fn test_bad_movement() {
+        let e = parse("F10\nU3").unwrap_err();
+        assert_eq!((e.line, e.column, e.text.as_str()), (2, 1, "U"));
+        let e = parse("F10\nR45").unwrap_err();
+        assert_eq!((e.line, e.column, e.text.as_str()), (2, 2, "45"));
+    }
+
+    #[test]
+    fn test_turns() {
+        assert!(matches!(Facing::W.new_direction(&90), Some(Facing::N)));
+        assert!(matches!(Facing::N.new_direction(&-450), Some(Facing::W)));
+        assert!(Facing::N.new_direction(&45).is_none());
+        let mut position = Position::new();
+        assert_eq!(position.travel(&Movement::Left(45)), None);
+        assert_eq!(position.travel_waypoint(&Movement::Right(30)), None);
+        assert_eq!(position.travel_waypoint(&Movement::Left(90)), Some(()));
+        assert_eq!((position.waypoint_x, position.waypoint_y), (-1, 10));
+        assert_eq!(position.travel(&Movement::Left(i32::MIN)), None);
+        assert!(matches!(position.facing, Facing::E));
     }
 }
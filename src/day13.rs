@@ -1,23 +1,43 @@
+use crate::error::{missing, parse_number, ParseError, ParseErrorKind};
 use std::collections::HashMap;
-use std::usize::MAX;
 
+/// The earliest departure time and each bus id keyed by its position in the schedule.
+pub type Notes = (usize, HashMap<usize, usize>);
+
+/// Parses the earliest departure time and the bus schedule, skipping `x` entries.
 #[aoc_generator(day13)]
-fn to_vec(input: &str) -> (usize, HashMap<usize, usize>) {
-    let arrival: usize = input.lines().next().unwrap().parse().unwrap();
+pub fn parse(input: &str) -> Result<Notes, ParseError> {
+    let mut lines = input.lines();
+    let arrival_line = lines.next().unwrap_or_default();
+    let arrival: usize = parse_number(13, input, arrival_line.trim())?;
+    let bus_line = lines.next().ok_or_else(|| missing(13, input, "bus ids"))?;
     let mut busses = HashMap::new();
-    for (i, s) in input.lines().skip(1).next().unwrap().split(",").enumerate() {
+    for (i, s) in bus_line.trim_end().split(',').enumerate() {
         if s != "x" {
-            busses.insert(i, s.parse().unwrap());
+            let bus = parse_number(13, input, s)?;
+            if bus == 0 {
+                return Err(ParseError::located(
+                    13,
+                    input,
+                    s,
+                    ParseErrorKind::UnexpectedToken,
+                ));
+            }
+            busses.insert(i, bus);
         }
     }
-    (arrival, busses)
+    if busses.is_empty() {
+        return Err(missing(13, input, "bus ids"));
+    }
+    Ok((arrival, busses))
 }
 
+/// The id of the earliest bus multiplied by the minutes waited for it.
 #[aoc(day13, part1)]
-fn bus_wait_time(input: &(usize, HashMap<usize, usize>)) -> usize {
+pub fn part1(input: &Notes) -> usize {
     let time = input.0;
     let busses = &input.1;
-    let mut min_time = MAX;
+    let mut min_time = usize::MAX;
     let mut best_bus_id = 0;
     for (&i, bus) in busses.iter() {
         let r = time % *bus;
@@ -30,8 +50,9 @@ fn bus_wait_time(input: &(usize, HashMap<usize, usize>)) -> usize {
     busses[&best_bus_id] * (min_time - time)
 }
 
+/// The earliest timestamp at which each bus departs at its offset in the schedule.
 #[aoc(day13, part2)]
-fn magic_timestamp(input: &(usize, HashMap<usize, usize>)) -> usize {
+pub fn part2(input: &Notes) -> usize {
     let mut buses: Vec<(usize, usize)> = input.1.iter().map(|(&pos, &id)| (pos, id)).collect();
     buses.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
     let mut timestamp = 0;
@@ -56,14 +77,20 @@ mod tests {
 
     #[test]
     fn earliest_bus() {
-        let input = to_vec(TEST_INPUT);
-        assert_eq!(bus_wait_time(&input), 295);
+        let input = parse(TEST_INPUT).unwrap();
+        assert_eq!(part1(&input), 295);
     }
 
     #[test]
     fn test_magic_timestamp() {
-        let input = to_vec(TEST_INPUT);
-        let result = magic_timestamp(&input);
+        let input = parse(TEST_INPUT).unwrap();
+        let result = part2(&input);
         assert_eq!(result, 1068781);
     }
+
+    #[test]
+    fn test_bad_bus() {
+        let e = parse("939\n7,13,x,y,59").unwrap_err();
+        assert_eq!((e.line, e.column, e.text.as_str()), (2, 8, "y"));
+    }
 }
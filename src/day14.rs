@@ -1,20 +1,28 @@
+use crate::error::{parse_number, ParseError, ParseErrorKind};
 use bitvec::prelude::*;
 use regex;
 use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
 
-struct MaskAndValues {
-    mask: HashMap<u8, u8>,
-    values: Vec<(usize, usize)>,
+/// A `mask = ...` line and the `mem[address] = value` writes that follow it.
+#[derive(Debug)]
+pub struct MaskAndValues {
+    /// The fixed bits of the mask, by bit position (0 is least significant); `X` bits are absent.
+    pub mask: HashMap<u8, u8>,
+    /// The `(address, value)` writes in program order.
+    pub values: Vec<(usize, usize)>,
 }
 
 impl MaskAndValues {
-    fn add_values(&self, totals: &mut HashMap<usize, usize>) {
+    /// Writes each value, with the mask applied, into `totals`.
+    pub fn add_values(&self, totals: &mut HashMap<usize, usize>) {
         for (key, value) in &self.values {
             totals.insert(*key, self.masked_value(value));
         }
     }
 
-    fn add_values_multiple_places(&self, totals: &mut HashMap<usize, usize>) {
+    /// Writes each value into every address the mask decodes its address to.
+    pub fn add_values_multiple_places(&self, totals: &mut HashMap<usize, usize>) {
         for (key, value) in &self.values {
             let keys = self.get_keys(key);
             for k in keys {
@@ -40,12 +48,12 @@ impl MaskAndValues {
         }
         values.insert(bits.load::<usize>());
         MaskAndValues::masking_recur(
-            &self.mask.keys().map(|v| *v).collect(),
+            &self.mask.keys().copied().collect(),
             &mut bits,
             0,
             &mut values,
         );
-        values.iter().map(|v| *v).collect()
+        values.iter().copied().collect()
     }
 
     fn masking_recur(
@@ -58,7 +66,7 @@ impl MaskAndValues {
             return;
         }
         let mut curr_bit = curr_bit;
-        while mask.contains(&(curr_bit as u8)) {
+        while mask.contains(&curr_bit) {
             curr_bit += 1;
         }
         if curr_bit > 35 {
@@ -82,39 +90,85 @@ impl MaskAndValues {
     }
 }
 
-impl From<&str> for MaskAndValues {
-    fn from(s: &str) -> Self {
-        let r = regex::Regex::new(r"mem\[(\d+)\] = (\d+)").unwrap();
+impl FromStr for MaskAndValues {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let r = regex::Regex::new(r"^mem\[(\d+)\] = (\d+)$").unwrap();
         let mut lines = s.lines();
+        let mask_line = lines.next().unwrap_or_default();
+        let mask_str = mask_line
+            .strip_prefix("mask = ")
+            .ok_or_else(|| ParseError::located(14, s, mask_line, ParseErrorKind::UnexpectedToken))?
+            .trim_end();
+        if mask_str.len() != 36 {
+            return Err(ParseError::located(
+                14,
+                s,
+                mask_str,
+                ParseErrorKind::UnexpectedToken,
+            ));
+        }
         let mut mask = HashMap::new();
         let mut curr_bit = 36;
-        for v in lines.next().unwrap().split("").filter(|s| !s.is_empty()) {
+        for (i, v) in mask_str.char_indices() {
             curr_bit -= 1;
-            if v != "X" {
-                mask.insert(curr_bit, v.parse().unwrap());
+            match v {
+                'X' => {}
+                '0' => {
+                    mask.insert(curr_bit, 0);
+                }
+                '1' => {
+                    mask.insert(curr_bit, 1);
+                }
+                _ => {
+                    let part = &mask_str[i..i + v.len_utf8()];
+                    return Err(ParseError::located(
+                        14,
+                        s,
+                        part,
+                        ParseErrorKind::UnexpectedToken,
+                    ));
+                }
             }
         }
         let mut values: Vec<(usize, usize)> = vec![];
-        for l in lines {
-            if let Some(captures) = r.captures(l) {
-                values.push((captures[1].parse().unwrap(), captures[2].parse().unwrap()));
-            }
+        for l in lines.map(str::trim_end).filter(|l| !l.is_empty()) {
+            let captures = r
+                .captures(l)
+                .ok_or_else(|| ParseError::located(14, s, l, ParseErrorKind::UnexpectedToken))?;
+            let key = parse_number(14, s, captures.get(1).unwrap().as_str())?;
+            let value = parse_number(14, s, captures.get(2).unwrap().as_str())?;
+            values.push((key, value));
         }
-        MaskAndValues { mask, values }
+        Ok(MaskAndValues { mask, values })
     }
 }
 
+/// Parses the initialization program into its mask blocks.
 #[aoc_generator(day14)]
-fn to_vec(input: &str) -> Vec<MaskAndValues> {
-    input
-        .split("mask = ")
-        .filter(|s| !s.is_empty())
-        .map(|i| i.into())
+pub fn parse(input: &str) -> Result<Vec<MaskAndValues>, ParseError> {
+    let mut starts: Vec<(usize, usize)> = vec![];
+    for (i, l) in input.lines().enumerate() {
+        if l.starts_with("mask") || (starts.is_empty() && !l.trim().is_empty()) {
+            starts.push((i, l.as_ptr() as usize - input.as_ptr() as usize));
+        }
+    }
+    starts
+        .iter()
+        .enumerate()
+        .map(|(n, &(line, from))| {
+            let to = starts.get(n + 1).map_or(input.len(), |&(_, to)| to);
+            input[from..to]
+                .parse()
+                .map_err(|e: ParseError| e.offset(line))
+        })
         .collect()
 }
 
+/// The sum of memory after running the program with masks applied to values.
 #[aoc(day14, part1)]
-fn masked_bit_sums(input: &Vec<MaskAndValues>) -> usize {
+pub fn part1(input: &[MaskAndValues]) -> usize {
     let mut vals: HashMap<usize, usize> = HashMap::new();
     for v in input {
         v.add_values(&mut vals);
@@ -122,8 +176,9 @@ fn masked_bit_sums(input: &Vec<MaskAndValues>) -> usize {
     vals.values().sum()
 }
 
+/// The sum of memory after running the program with masks applied to addresses.
 #[aoc(day14, part2)]
-fn data_mask_sums(input: &Vec<MaskAndValues>) -> usize {
+pub fn part2(input: &[MaskAndValues]) -> usize {
     let mut vals: HashMap<usize, usize> = HashMap::new();
     for v in input {
         v.add_values_multiple_places(&mut vals);
@@ -149,13 +204,26 @@ mem[26] = 1
 
     #[test]
     fn test_bit_sum() {
-        let input = to_vec(TEST_INPUT);
-        assert_eq!(masked_bit_sums(&input), 165);
+        let input = parse(TEST_INPUT).unwrap();
+        assert_eq!(part1(&input), 165);
     }
 
     #[test]
     fn test_bit_multi_sum() {
-        let input = to_vec(OTHER_TEST_INPUT);
-        assert_eq!(data_mask_sums(&input), 208);
+        let input = parse(OTHER_TEST_INPUT).unwrap();
+        assert_eq!(part2(&input), 208);
+    }
+
+    #[test]
+    fn test_bad_program() {
+        let e = parse("mask = XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX0X\nmem[8] = 11\nmem[7] 101")
+            .unwrap_err();
+        assert_eq!((e.line, e.column, e.text.as_str()), (3, 1, "mem[7] 101"));
+        let e = parse("mem[8] = 11").unwrap_err();
+        assert_eq!(e.line, 1);
+        let e = parse("mem[8] = 11\nmask = XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX2X").unwrap_err();
+        assert_eq!(e.line, 1);
+        let e = parse("mask = XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX2X").unwrap_err();
+        assert_eq!((e.line, e.column, e.text.as_str()), (1, 42, "2"));
     }
 }
@@ -1,15 +1,23 @@
+use crate::error::{missing, parse_number, ParseError};
 use std::collections::HashMap;
 
+/// Parses the comma separated starting numbers.
 #[aoc_generator(day15)]
-fn to_vec(input: &str) -> Vec<usize> {
-    input
-        .split(",")
+pub fn parse(input: &str) -> Result<Vec<usize>, ParseError> {
+    let numbers: Vec<usize> = input
+        .trim_end()
+        .split(',')
         .filter(|s| !s.is_empty())
-        .map(|i| i.parse().unwrap())
-        .collect()
+        .map(|i| parse_number(15, input, i.trim()))
+        .collect::<Result<_, _>>()?;
+    if numbers.is_empty() {
+        return Err(missing(15, input, "starting numbers"));
+    }
+    Ok(numbers)
 }
 
-fn last_spoken(input: &Vec<usize>, last: usize) -> usize {
+/// The number spoken on turn `last` of the memory game.
+pub fn last_spoken(input: &[usize], last: usize) -> usize {
     let mut turns_spoken: HashMap<usize, usize> = input
         .iter()
         .take(input.len() - 1)
@@ -23,18 +31,20 @@ fn last_spoken(input: &Vec<usize>, last: usize) -> usize {
             None => 0,
         };
         turns_spoken.insert(last_spoken, i - 1);
-        last_spoken = newly_spoken as usize;
+        last_spoken = newly_spoken;
     }
-    return last_spoken;
+    last_spoken
 }
 
+/// The 2020th number spoken.
 #[aoc(day15, part1)]
-fn number_spoken(input: &Vec<usize>) -> usize {
+pub fn part1(input: &[usize]) -> usize {
     last_spoken(input, 2020)
 }
 
+/// The 30000000th number spoken.
 #[aoc(day15, part2)]
-fn number_spoken_big(input: &Vec<usize>) -> usize {
+pub fn part2(input: &[usize]) -> usize {
     last_spoken(input, 30000000)
 }
 
@@ -44,13 +54,20 @@ mod tests {
 
     #[test]
     fn test_number_spoken() {
-        assert_eq!(number_spoken(&to_vec("0,3,6")), 436);
-        assert_eq!(number_spoken(&to_vec("1,3,2")), 1);
-        assert_eq!(number_spoken(&to_vec("1,2,3")), 27);
+        assert_eq!(part1(&parse("0,3,6").unwrap()), 436);
+        assert_eq!(part1(&parse("1,3,2").unwrap()), 1);
+        assert_eq!(part1(&parse("1,2,3").unwrap()), 27);
     }
 
     #[test]
     fn test_number_spoken_big() {
-        assert_eq!(number_spoken_big(&to_vec("0,3,6")), 175594);
+        assert_eq!(part2(&parse("0,3,6").unwrap()), 175594);
+    }
+
+    #[test]
+    fn test_bad_numbers() {
+        let e = parse("0,3,-6").unwrap_err();
+        assert_eq!((e.line, e.column, e.text.as_str()), (1, 5, "-6"));
+        assert!(parse("\n").is_err());
     }
 }
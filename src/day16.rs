@@ -1,71 +1,103 @@
+use crate::error::{missing, parse_lines, parse_number, records, ParseError, ParseErrorKind};
 use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
 
-#[derive(Hash, Eq, PartialEq)]
-struct Field {
-    name: String,
-    range_1: (usize, usize),
-    range_2: (usize, usize),
+/// A ticket field rule: its name and the two inclusive ranges its values may fall in.
+#[derive(Debug, Hash, Eq, PartialEq)]
+pub struct Field {
+    pub name: String,
+    pub range_1: (usize, usize),
+    pub range_2: (usize, usize),
 }
 
-impl From<&str> for Field {
-    fn from(s: &str) -> Self {
-        let mut colon = s.split(":");
-        let name = colon.next().unwrap();
-        let ranges = colon.next().unwrap();
+impl FromStr for Field {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut colon = s.splitn(2, ':');
+        let name = colon.next().unwrap_or_default();
+        let ranges = colon.next().ok_or_else(|| missing(16, s, "field ranges"))?;
         let mut range_strs = ranges.split(" or ");
-        let range_1: Vec<usize> = range_strs
-            .next()
-            .unwrap()
-            .split("-")
-            .map(|split| split.trim().parse().unwrap())
-            .collect();
-        let range_2: Vec<usize> = range_strs
-            .next()
-            .unwrap()
-            .split("-")
-            .map(|split| split.trim().parse().unwrap())
-            .collect();
-        Field {
+        let mut next_range = || -> Result<(usize, usize), ParseError> {
+            let range = range_strs
+                .next()
+                .ok_or_else(|| missing(16, s, "field range"))?;
+            let mut bounds = range.splitn(2, '-');
+            let low = parse_number(16, s, bounds.next().unwrap_or_default().trim())?;
+            let high = bounds
+                .next()
+                .ok_or_else(|| {
+                    ParseError::located(16, s, range, ParseErrorKind::Missing("range end"))
+                })
+                .and_then(|b| parse_number(16, s, b.trim()))?;
+            Ok((low, high))
+        };
+        let range_1 = next_range()?;
+        let range_2 = next_range()?;
+        Ok(Field {
             name: String::from(name),
-            range_1: (range_1[0], range_1[1]),
-            range_2: (range_2[0], range_2[1]),
-        }
+            range_1,
+            range_2,
+        })
     }
 }
 
 impl Field {
-    fn is_invalid_number(&self, n: &usize) -> bool {
+    /// Whether `n` falls outside both of the field's ranges.
+    pub fn is_invalid_number(&self, n: &usize) -> bool {
         !((*n >= self.range_1.0 && *n <= self.range_1.1)
             || (*n >= self.range_2.0 && *n <= self.range_2.1))
     }
 }
 
+fn to_ticket(s: &str, len: usize) -> Result<Vec<usize>, ParseError> {
+    let ticket: Vec<usize> = s
+        .trim_end()
+        .split(',')
+        .map(|n| parse_number(16, s, n.trim()))
+        .collect::<Result<_, _>>()?;
+    if ticket.len() != len {
+        return Err(ParseError::located(
+            16,
+            s,
+            s,
+            ParseErrorKind::Missing("ticket values"),
+        ));
+    }
+    Ok(ticket)
+}
+
+/// The field rules, your ticket and the nearby tickets.
+pub type Notes = (Vec<Field>, Vec<usize>, Vec<Vec<usize>>);
+
+/// Parses the three sections of the ticket notes.
 #[aoc_generator(day16)]
-fn to_vec(input: &str) -> (Vec<Field>, Vec<usize>, Vec<Vec<usize>>) {
-    let mut splits = input.split("\n\n");
-    let fields: Vec<Field> = splits.next().unwrap().lines().map(|s| s.into()).collect();
-    let my_ticket: Vec<usize> = splits
-        .next()
-        .unwrap()
-        .lines()
-        .skip(1)
+pub fn parse(input: &str) -> Result<Notes, ParseError> {
+    let mut splits = records(input).into_iter();
+    let (line, section) = splits.next().ok_or_else(|| missing(16, input, "fields"))?;
+    let fields: Vec<Field> = parse_lines(section, str::parse).map_err(|e| e.offset(line))?;
+    let (line, section) = splits
         .next()
-        .unwrap()
-        .split(",")
-        .map(|s| s.parse().unwrap())
-        .collect();
-    let other_tickets: Vec<Vec<usize>> = splits
+        .ok_or_else(|| missing(16, input, "your ticket"))?;
+    let my_ticket: Vec<usize> = match section.lines().nth(1) {
+        Some(l) => to_ticket(l, fields.len()).map_err(|e| e.offset(line + 1))?,
+        None => return Err(missing(16, section, "your ticket").offset(line)),
+    };
+    let (line, section) = splits
         .next()
-        .unwrap()
+        .ok_or_else(|| missing(16, input, "nearby tickets"))?;
+    let other_tickets: Vec<Vec<usize>> = section
         .lines()
+        .enumerate()
         .skip(1)
-        .map(|s| s.split(",").map(|s| s.parse().unwrap()).collect())
-        .collect();
-    (fields, my_ticket, other_tickets)
+        .map(|(i, l)| to_ticket(l, fields.len()).map_err(|e| e.offset(line + i)))
+        .collect::<Result<_, _>>()?;
+    Ok((fields, my_ticket, other_tickets))
 }
 
+/// The sum of the nearby ticket values that are not valid for any field.
 #[aoc(day16, part1)]
-fn sum_invalid_tickets(input: &(Vec<Field>, Vec<usize>, Vec<Vec<usize>>)) -> usize {
+pub fn part1(input: &Notes) -> usize {
     let mut sum = 0;
     for vec in &input.2 {
         for &v in vec {
@@ -77,14 +109,15 @@ fn sum_invalid_tickets(input: &(Vec<Field>, Vec<usize>, Vec<Vec<usize>>)) -> usi
     sum
 }
 
+/// The product of the values on your ticket for the fields whose name contains `departure`.
 #[aoc(day16, part2)]
-fn departure_products(input: &(Vec<Field>, Vec<usize>, Vec<Vec<usize>>)) -> usize {
+pub fn part2(input: &Notes) -> usize {
     let valid_tickets: Vec<&Vec<usize>> = input
         .2
         .iter()
         .filter(|&vec| {
             !vec.iter()
-                .any(|v| input.0.iter().all(|f| f.is_invalid_number(&v)))
+                .any(|v| input.0.iter().all(|f| f.is_invalid_number(v)))
         })
         .collect();
     let mut field_translation = HashMap::new();
@@ -142,9 +175,9 @@ nearby tickets:
 55,2,20
 38,6,12";
 
-    const OTHER_TEST_INPUT: &str = "class: 0-1 or 4-19
+    const OTHER_TEST_INPUT: &str = "departure class: 0-1 or 4-19
 row: 0-5 or 8-19
-seat: 0-13 or 16-19
+departure seat: 0-13 or 16-19
 
 your ticket:
 11,12,13
@@ -156,11 +189,21 @@ nearby tickets:
 
     #[test]
     fn test_number_spoken() {
-        assert_eq!(sum_invalid_tickets(&to_vec(TEST_INPUT)), 71);
+        assert_eq!(part1(&parse(TEST_INPUT).unwrap()), 71);
     }
 
     #[test]
     fn test_departure_prod() {
-        assert_eq!(departure_products(&to_vec(OTHER_TEST_INPUT)), 71);
+        assert_eq!(part2(&parse(OTHER_TEST_INPUT).unwrap()), 12 * 13);
+    }
+
+    #[test]
+    fn test_bad_notes() {
+        let e = parse(&TEST_INPUT.replace("6-11", "6-1l")).unwrap_err();
+        assert_eq!((e.line, e.column, e.text.as_str()), (2, 8, "1l"));
+        let e = parse(&TEST_INPUT.replace("40,4,50", "40,4")).unwrap_err();
+        assert_eq!((e.line, e.column), (10, 1));
+        let e = parse(&TEST_INPUT.replace("7,1,14", "7,1,x")).unwrap_err();
+        assert_eq!((e.line, e.column, e.text.as_str()), (6, 5, "x"));
     }
 }
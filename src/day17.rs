@@ -1,395 +1,102 @@
-use std::collections::HashMap;
-
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
-enum CubeState {
-    On,
-    Off,
-}
-
-impl From<&str> for CubeState {
-    fn from(s: &str) -> Self {
-        match s {
-            "#" => CubeState::On,
-            "." => CubeState::Off,
-            _ => unimplemented!(),
-        }
-    }
-}
-
-struct CoordinateDirection3d<'a> {
-    coordinates: [i32; 3],
-    direction: [i8; 3],
-    maximum: &'a [i32; 3],
-    minimum: &'a [i32; 3],
-}
-
-impl Iterator for CoordinateDirection3d<'_> {
-    type Item = [i32; 3];
-
-    fn next(&mut self) -> Option<[i32; 3]> {
-        for (i, &d) in self.direction.iter().enumerate() {
-            if (d < 0 && self.minimum[i] == self.coordinates[i])
-                || (d > 0 && self.maximum[i] == self.coordinates[i])
-            {
-                return None;
-            }
-        }
-        self.coordinates = [
-            self.coordinates[0] + self.direction[0] as i32,
-            self.coordinates[1] + self.direction[1] as i32,
-            self.coordinates[2] + self.direction[2] as i32,
-        ];
-        Some(self.coordinates)
-    }
-}
-
-struct CoordinateDirection4d<'a> {
-    coordinates: [i32; 4],
-    direction: [i8; 4],
-    maximum: &'a [i32; 4],
-    minimum: &'a [i32; 4],
-}
-
-impl Iterator for CoordinateDirection4d<'_> {
-    type Item = [i32; 4];
-
-    fn next(&mut self) -> Option<[i32; 4]> {
-        for (i, &d) in self.direction.iter().enumerate() {
-            if (d < 0 && self.minimum[i] == self.coordinates[i])
-                || (d > 0 && self.maximum[i] == self.coordinates[i])
-            {
-                return None;
-            }
-        }
-        self.coordinates = [
-            self.coordinates[0] + self.direction[0] as i32,
-            self.coordinates[1] + self.direction[1] as i32,
-            self.coordinates[2] + self.direction[2] as i32,
-            self.coordinates[3] + self.direction[3] as i32,
-        ];
-        Some(self.coordinates)
-    }
-}
-
-#[derive(Debug, Copy, Clone)]
-struct Cube {
-    state: CubeState,
-    coordinates: [i32; 3],
-}
-
-#[derive(Debug, Copy, Clone)]
-struct HyperCube {
-    state: CubeState,
-    coordinates: [i32; 4],
-}
-
-fn all_coordinate_generators<'a>(
-    cube: &'a Cube,
-    maximum: &'a [i32; 3],
-    minimum: &'a [i32; 3],
-) -> Vec<CoordinateDirection3d<'a>> {
-    let iterator_generator = |delta| CoordinateDirection3d {
-        coordinates: cube.coordinates.clone(),
-        maximum: &maximum,
-        minimum: &minimum,
-        direction: delta,
-    };
-    let mut v = vec![];
-    for x in -1..=1 {
-        for y in -1..=1 {
-            for z in -1..=1 {
-                if x != 0 || y != 0 || z != 0 {
-                    v.push(iterator_generator([x, y, z]));
-                }
-            }
-        }
-    }
-    v
-}
-
-fn all_4d_coordinate_generators<'a>(
-    cube: &'a HyperCube,
-    maximum: &'a [i32; 4],
-    minimum: &'a [i32; 4],
-) -> Vec<CoordinateDirection4d<'a>> {
-    let iterator_generator = |delta| CoordinateDirection4d {
-        coordinates: cube.coordinates.clone(),
-        maximum: &maximum,
-        minimum: &minimum,
-        direction: delta,
-    };
-    let mut v = vec![];
-    for x in -1..=1 {
-        for y in -1..=1 {
-            for z in -1..=1 {
-                for w in -1..=1 {
-                    if x != 0 || y != 0 || z != 0 || w != 0 {
-                        v.push(iterator_generator([x, y, z, w]));
+use crate::error::ParseError;
+use crate::grid::BitGrid;
+
+/// The coordinates of `layer` in each of `dims` extra dimensions, each `size` wide.
+fn layer_coordinates(mut layer: usize, dims: u32, size: usize) -> Vec<usize> {
+    (0..dims)
+        .map(|_| {
+            let c = layer % size;
+            layer /= size;
+            c
+        })
+        .collect()
+}
+
+/// How many cubes around `(x, y)` of `layer` are active, counting across every adjacent layer of
+/// a space with `dims` extra dimensions, each `size` layers deep.
+fn active_neighbours(
+    layers: &[BitGrid],
+    dims: u32,
+    size: usize,
+    layer: usize,
+    x: usize,
+    y: usize,
+) -> usize {
+    let coordinates = layer_coordinates(layer, dims, size);
+    let mut count = 0;
+    for offset in 0..3usize.pow(dims) {
+        let deltas = layer_coordinates(offset, dims, 3);
+        let neighbour = coordinates
+            .iter()
+            .zip(&deltas)
+            .rev()
+            .try_fold(0, |i, (&c, &d)| {
+                let c = (c + d).checked_sub(1).filter(|&c| c < size)?;
+                Some(i * size + c)
+            });
+        if let Some(n) = neighbour {
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    let itself = n == layer && dx == 0 && dy == 0;
+                    if !itself && layers[n].get(x as isize + dx, y as isize + dy) == Some(true) {
+                        count += 1;
                     }
                 }
             }
         }
     }
-    v
+    count
 }
 
-const DEFAULT_CUBE: Cube = Cube {
-    coordinates: [0; 3],
-    state: CubeState::Off,
-};
-
-const DEFAULT_HYPER_CUBE: HyperCube = HyperCube {
-    coordinates: [0; 4],
-    state: CubeState::Off,
-};
-
-impl Cube {
-    fn new_state(
-        &self,
-        max_coordinates: &[i32; 3],
-        min_coordinates: &[i32; 3],
-        space: &HashMap<[i32; 3], Cube>,
-    ) -> Self {
-        let mut visual_iters = all_coordinate_generators(&self, max_coordinates, min_coordinates);
-        let mut active_count = 0;
-        for coor_iter in visual_iters.iter_mut() {
-            if let Some(c) = coor_iter.next() {
-                if space.get(&c).unwrap_or(&DEFAULT_CUBE).state == CubeState::On {
-                    active_count += 1;
-                }
-            }
-        }
-        let mut state = self.state.clone();
-        if self.state == CubeState::On {
-            if !(active_count == 3 || active_count == 2) {
-                state = CubeState::Off;
+/// Runs `cycles` cycles starting from the flat slice `start` in a pocket dimension with
+/// `extra_dims` dimensions beyond the two of the slice, returning how many cubes are active.
+///
+/// Cubes can spread at most one square per cycle, so the space is sized up front: the slice is
+/// padded by `cycles` on every side and each extra dimension is `2 * cycles + 1` layers deep.
+pub fn simulate(start: &BitGrid, extra_dims: u32, cycles: usize) -> usize {
+    let size = 2 * cycles + 1;
+    let (width, height) = (start.width() + 2 * cycles, start.height() + 2 * cycles);
+    let mut layers = vec![BitGrid::new(width, height); size.pow(extra_dims)];
+    let centre = (0..extra_dims).fold(0, |i, _| i * size + cycles);
+    for y in 0..start.height() {
+        for x in 0..start.width() {
+            if start.get(x as isize, y as isize) == Some(true) {
+                layers[centre].set(x + cycles, y + cycles, true);
             }
         }
-        if self.state == CubeState::Off && active_count == 3 {
-            state = CubeState::On;
-        }
-        Cube {
-            coordinates: self.coordinates.clone(),
-            state,
-        }
     }
-}
-
-impl HyperCube {
-    fn new_state(
-        &self,
-        max_coordinates: &[i32; 4],
-        min_coordinates: &[i32; 4],
-        space: &HashMap<[i32; 4], HyperCube>,
-    ) -> Self {
-        let mut visual_iters =
-            all_4d_coordinate_generators(&self, max_coordinates, min_coordinates);
-        let mut active_count = 0;
-        for coor_iter in visual_iters.iter_mut() {
-            if let Some(c) = coor_iter.next() {
-                if space.get(&c).unwrap_or(&DEFAULT_HYPER_CUBE).state == CubeState::On {
-                    active_count += 1;
+    for _ in 0..cycles {
+        let mut next = layers.clone();
+        for (layer, grid) in next.iter_mut().enumerate() {
+            for y in 0..height {
+                for x in 0..width {
+                    let active = layers[layer].get(x as isize, y as isize) == Some(true);
+                    let neighbours = active_neighbours(&layers, extra_dims, size, layer, x, y);
+                    grid.set(x, y, neighbours == 3 || (active && neighbours == 2));
                 }
             }
         }
-        let mut state = self.state.clone();
-        if self.state == CubeState::On {
-            if !(active_count == 3 || active_count == 2) {
-                state = CubeState::Off;
-            }
-        }
-        if self.state == CubeState::Off && active_count == 3 {
-            state = CubeState::On;
-        }
-        HyperCube {
-            coordinates: self.coordinates.clone(),
-            state,
-        }
+        layers = next;
     }
+    layers.iter().map(BitGrid::count_ones).sum()
 }
 
-#[aoc_generator(day17, part1)]
-fn to_vec(input: &str) -> (HashMap<[i32; 3], Cube>, [i32; 3]) {
-    let mut space: HashMap<[i32; 3], Cube> = HashMap::new();
-    let mut y = 0;
-    let mut max_x = 0;
-    for l in input.lines() {
-        for (x, s) in l.split("").filter(|&s| !s.is_empty()).enumerate() {
-            space.insert(
-                [x as i32, y, 0],
-                Cube {
-                    state: s.into(),
-                    coordinates: [x as i32, y, 0],
-                },
-            );
-            max_x = x.max(max_x);
-        }
-        y += 1;
-    }
-    (space, [max_x as i32, y, 0])
+/// Parses the initial flat slice of the pocket dimension, `#` for an active cube.
+#[aoc_generator(day17)]
+pub fn parse(input: &str) -> Result<BitGrid, ParseError> {
+    BitGrid::parse(17, input)
 }
 
+/// How many cubes are active after six cycles in three dimensions.
 #[aoc(day17, part1)]
-fn active_state_count(input: &(HashMap<[i32; 3], Cube>, [i32; 3])) -> usize {
-    let mut curr_vals = input.0.clone();
-    let mut z_max = 1;
-    let mut z_min = -1;
-    let mut y_max = input.1[1] + 1;
-    let mut y_min = -1;
-    let mut x_max = input.1[0] + 1;
-    let mut x_min = -1;
-    for x in x_min..=x_max {
-        for y in y_min..=y_max {
-            for z in z_min..=z_max {
-                let coors = [x, y, z];
-                curr_vals.entry(coors.clone()).or_insert(Cube {
-                    coordinates: coors,
-                    state: CubeState::Off,
-                });
-            }
-        }
-    }
-    for &x in &[-1, input.1[0]] {
-        for &y in &[-1, input.1[1]] {
-            let coors = [x, y, 0];
-            curr_vals.insert(
-                coors.clone(),
-                Cube {
-                    coordinates: coors,
-                    state: CubeState::Off,
-                },
-            );
-        }
-    }
-    for _ in 0..6 {
-        let mut new_vals = curr_vals.clone();
-        let max_coors = [x_max, y_max, z_max];
-        let min_coors = [x_min, y_min, z_min];
-        for (&coors, &cube) in &curr_vals {
-            let new_cube = cube.new_state(&max_coors, &min_coors, &curr_vals);
-            new_vals.insert(coors, new_cube);
-            if new_cube.state == CubeState::On {
-                z_max = z_max.max(new_cube.coordinates[2] + 1);
-                y_max = y_max.max(new_cube.coordinates[1] + 1);
-                x_max = x_max.max(new_cube.coordinates[0] + 1);
-                z_min = z_min.min(new_cube.coordinates[2] - 1);
-                y_min = y_min.min(new_cube.coordinates[1] - 1);
-                x_min = x_min.min(new_cube.coordinates[0] - 1);
-            }
-        }
-        for x in x_min..=x_max {
-            for y in y_min..=y_max {
-                for z in z_min..=z_max {
-                    let coors = [x, y, z];
-                    new_vals.entry(coors.clone()).or_insert(Cube {
-                        coordinates: coors,
-                        state: CubeState::Off,
-                    });
-                }
-            }
-        }
-        curr_vals = new_vals;
-    }
-    curr_vals
-        .values()
-        .filter(|&c| c.state == CubeState::On)
-        .count()
-}
-
-#[aoc_generator(day17, part2)]
-fn to_hyper_vec(input: &str) -> (HashMap<[i32; 4], HyperCube>, [i32; 4]) {
-    let mut space: HashMap<[i32; 4], HyperCube> = HashMap::new();
-    let mut y = 0;
-    let mut max_x = 0;
-    for l in input.lines() {
-        for (x, s) in l.split("").filter(|&s| !s.is_empty()).enumerate() {
-            space.insert(
-                [x as i32, y, 0, 0],
-                HyperCube {
-                    state: s.into(),
-                    coordinates: [x as i32, y, 0, 0],
-                },
-            );
-            max_x = x.max(max_x);
-        }
-        y += 1;
-    }
-    (space, [max_x as i32, y, 0, 0])
+pub fn part1(input: &BitGrid) -> usize {
+    simulate(input, 1, 6)
 }
 
+/// How many hypercubes are active after six cycles in four dimensions.
 #[aoc(day17, part2)]
-fn hyper_active_state_count(input: &(HashMap<[i32; 4], HyperCube>, [i32; 4])) -> usize {
-    let mut curr_vals = input.0.clone();
-    let mut w_max = 1;
-    let mut w_min = -1;
-    let mut z_max = 1;
-    let mut z_min = -1;
-    let mut y_max = input.1[1] + 1;
-    let mut y_min = -1;
-    let mut x_max = input.1[0] + 1;
-    let mut x_min = -1;
-    for x in x_min..=x_max {
-        for y in y_min..=y_max {
-            for z in z_min..=z_max {
-                for w in z_min..=z_max {
-                    let coors = [x, y, z, w];
-                    curr_vals.entry(coors.clone()).or_insert(HyperCube {
-                        coordinates: coors,
-                        state: CubeState::Off,
-                    });
-                }
-            }
-        }
-    }
-    for &x in &[-1, input.1[0]] {
-        for &y in &[-1, input.1[1]] {
-            let coors = [x, y, 0, 0];
-            curr_vals.insert(
-                coors.clone(),
-                HyperCube {
-                    coordinates: coors,
-                    state: CubeState::Off,
-                },
-            );
-        }
-    }
-    for _ in 0..6 {
-        let mut new_vals = curr_vals.clone();
-        let max_coors = [x_max, y_max, z_max, w_max];
-        let min_coors = [x_min, y_min, z_min, w_min];
-        for (&coors, &cube) in &curr_vals {
-            let new_cube = cube.new_state(&max_coors, &min_coors, &curr_vals);
-            new_vals.insert(coors, new_cube);
-            if new_cube.state == CubeState::On {
-                w_max = w_max.max(new_cube.coordinates[3] + 1);
-                z_max = z_max.max(new_cube.coordinates[2] + 1);
-                y_max = y_max.max(new_cube.coordinates[1] + 1);
-                x_max = x_max.max(new_cube.coordinates[0] + 1);
-                w_min = w_min.min(new_cube.coordinates[3] - 1);
-                z_min = z_min.min(new_cube.coordinates[2] - 1);
-                y_min = y_min.min(new_cube.coordinates[1] - 1);
-                x_min = x_min.min(new_cube.coordinates[0] - 1);
-            }
-        }
-        for x in x_min..=x_max {
-            for y in y_min..=y_max {
-                for z in z_min..=z_max {
-                    for w in w_min..=w_max {
-                        let coors = [x, y, z, w];
-                        new_vals.entry(coors.clone()).or_insert(HyperCube {
-                            coordinates: coors,
-                            state: CubeState::Off,
-                        });
-                    }
-                }
-            }
-        }
-        curr_vals = new_vals;
-    }
-    curr_vals
-        .values()
-        .filter(|&c| c.state == CubeState::On)
-        .count()
+pub fn part2(input: &BitGrid) -> usize {
+    simulate(input, 2, 6)
 }
 
 #[cfg(test)]
@@ -402,7 +109,14 @@ mod tests {
 
     #[test]
     fn test_count_after_cycles() {
-        assert_eq!(active_state_count(&to_vec(TEST_INPUT)), 112);
-        assert_eq!(hyper_active_state_count(&to_hyper_vec(TEST_INPUT)), 848);
+        let input = parse(TEST_INPUT).unwrap();
+        assert_eq!(part1(&input), 112);
+        assert_eq!(part2(&input), 848);
+    }
+
+    #[test]
+    fn test_bad_cube() {
+        let e = parse(".#.\n..#\n#o#").unwrap_err();
+        assert_eq!((e.line, e.column, e.text.as_str()), (3, 2, "o"));
     }
 }
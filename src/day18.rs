@@ -1,8 +1,8 @@
-use regex;
-use std::collections::VecDeque;
-use std::hint::unreachable_unchecked;
+use crate::error::{missing, parse_lines, parse_number, ParseError, ParseErrorKind};
 
-enum Token {
+/// A lexical element of a homework expression.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Token {
     Prod,
     Add,
     Num(u128),
@@ -10,246 +10,174 @@ enum Token {
     CloseParen,
 }
 
-fn do_math_from_str(line: &str) -> u128 {
-    let split = line.split(" ");
-    let mut tokens = VecDeque::new();
-    let open_paren_pattern = regex::Regex::new(r"(\(+)(\d+)").unwrap();
-    let close_paren_pattern = regex::Regex::new(r"(\d+)(\)+)").unwrap();
-    for s in split.filter(|&s| !s.is_empty()) {
-        if s.starts_with("(") {
-            let captures = open_paren_pattern.captures(s).unwrap();
-            for _ in 0..captures[1].len() {
-                tokens.push_back(Token::OpenParen);
-            }
-            tokens.push_back(Token::Num(captures[2].parse().unwrap()));
-        } else if s.ends_with(")") {
-            let captures = close_paren_pattern.captures(s).unwrap();
-            let num:u128 = captures[1].parse().unwrap();
-            let paren_count:usize = captures[2].len();
-            let operand = tokens.pop_back().unwrap();
-            let val = tokens.pop_back().unwrap();
-            if let Token::Num(other) = val {
-                let mut new = match operand {
-                    Token::Prod => num * other,
-                    Token::Add => num + other,
-                    _ => unreachable!()
-                };
-                let mut open_parent_count = 0;
-                while open_parent_count < paren_count {
-                    match tokens.pop_back().unwrap() {
-                        Token::OpenParen => open_parent_count += 1,
-                        Token::Prod => if let Token::Num(o) = tokens.pop_back().unwrap(){
-                            new *= o;
-                        },
-                        Token::Add => if let Token::Num(o) = tokens.pop_back().unwrap(){
-                            new += o;
-                        },
-                        _ => unreachable!()
-                    }
-                }
-                if let Some(t) = tokens.back() {
-                    match t {
-                        Token::Prod => {
-                            tokens.pop_back().unwrap();
-                            if let Token::Num(o) = tokens.pop_back().unwrap() {
-                                new *= o;
-                            }
-                        },
-                        Token::Add => {
-                            tokens.pop_back().unwrap();
-                            if let Token::Num(o) = tokens.pop_back().unwrap() {
-                                new += o;
-                            }
-                        },
-                        _ => {}
-                    }
-                }
-                tokens.push_back(Token::Num(new))
-            };
-        } else if s == "*" {
-            tokens.push_back(Token::Prod)
-        } else if s == "+" {
-            tokens.push_back(Token::Add)
-        } else { // its a number
-            let v: u128 = s.parse().unwrap();
-            if tokens.is_empty() {
-                tokens.push_back(Token::Num(v))
-            } else {
-                let operand = tokens.pop_back().unwrap();
-                let val = tokens.pop_back().unwrap();
-                if let Token::Num(other) = val {
-                    match operand {
-                        Token::Prod => tokens.push_back(Token::Num(v * other)),
-                        Token::Add => tokens.push_back(Token::Num(v + other)),
-                        _ => unreachable!()
-                    }
+fn tokenize(line: &str) -> Result<Vec<Token>, ParseError> {
+    let mut tokens = vec![];
+    let mut depth = 0;
+    let mut chars = line.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let unexpected = || {
+            ParseError::located(
+                18,
+                line,
+                &line[i..i + c.len_utf8()],
+                ParseErrorKind::UnexpectedToken,
+            )
+        };
+        // operands are expected at the start and after operators or an open paren
+        let want_operand = matches!(
+            tokens.last(),
+            None | Some(Token::Add) | Some(Token::Prod) | Some(Token::OpenParen)
+        );
+        let token = match c {
+            ' ' => continue,
+            '0'..='9' if want_operand => {
+                let mut end = i + 1;
+                while let Some(&(j, '0'..='9')) = chars.peek() {
+                    end = j + 1;
+                    chars.next();
                 }
+                Token::Num(parse_number(18, line, &line[i..end])?)
             }
-        }
-    }
-    if let Token::Num(val) = tokens.pop_back().unwrap() {
-        let mut result = val;
-        while !tokens.is_empty() {
-            let operand = tokens.pop_back().unwrap();
-            let val = tokens.pop_back().unwrap();
-            if let Token::Num(other) = val {
-                match operand {
-                    Token::Prod => result *= other,
-                    Token::Add => result += other,
-                    _ => unreachable!()
-                }
+            '(' if want_operand => {
+                depth += 1;
+                Token::OpenParen
             }
+            ')' if !want_operand && depth > 0 => {
+                depth -= 1;
+                Token::CloseParen
+            }
+            '+' if !want_operand => Token::Add,
+            '*' if !want_operand => Token::Prod,
+            _ => return Err(unexpected()),
+        };
+        tokens.push(token);
+    }
+    match tokens.last() {
+        None | Some(Token::Add) | Some(Token::Prod) | Some(Token::OpenParen) => {
+            Err(missing(18, line, "operand"))
         }
-        return result;
+        _ if depth > 0 => Err(missing(18, line, "closing parenthesis")),
+        _ => Ok(tokens),
     }
-    0
 }
 
-fn reduce(tokens: &[Token]) -> u128 {
-    let to_mul = vec![];
-    
+fn operand(tokens: &[Token], pos: &mut usize, add_first: bool) -> u128 {
+    let token = &tokens[*pos];
+    *pos += 1;
+    match token {
+        Token::Num(n) => *n,
+        Token::OpenParen => {
+            let val = expression(tokens, pos, add_first);
+            // skip the closing paren
+            *pos += 1;
+            val
+        }
+        _ => unreachable!(),
+    }
 }
 
-fn do_other_math_from_str(line: &str) -> u128 {
-    let mut split = line.split(" ");
-    let mut tokens = VecDeque::new();
-    let open_paren_pattern = regex::Regex::new(r"(\(+)(\d+)").unwrap();
-    let close_paren_pattern = regex::Regex::new(r"(\d+)(\)+)").unwrap();
-    for s in split.filter(|&s| !s.is_empty()) {
-        if s.starts_with("(") {
-            let captures = open_paren_pattern.captures(s).unwrap();
-            for _ in 0..captures[1].len() {
-                tokens.push_back(Token::OpenParen);
-            }
-            tokens.push_back(Token::Num(captures[2].parse().unwrap()));
-        } else if s.ends_with(")") {
-            let captures = close_paren_pattern.captures(s).unwrap();
-            let num:u128 = captures[1].parse().unwrap();
-            let paren_count:usize = captures[2].len();
-            if let Token::Add = tokens.back().unwrap() {
-                let operand = tokens.pop_back().unwrap();
-                let val = tokens.pop_back().unwrap();
-                if let Token::Num(other) = val {
-                    let mut new = match operand {
-                        Token::Prod => num * other,
-                        Token::Add => num + other,
-                        _ => unreachable!()
-                    };
-                let operand = tokens.pop_back().unwrap();
-                let val = tokens.pop_back().unwrap();
-                if let Token::Num(other) = val {
-                    let mut open_parent_count = 0;
-                    while open_parent_count < paren_count {
-                        match tokens.pop_back().unwrap() {
-                            Token::OpenParen => open_parent_count += 1,
-                            /*Token::Prod => if let Token::Num(o) = tokens.pop_back().unwrap(){
-                                new *= o;
-                            },*/
-                            Token::Add => if let Token::Num(o) = tokens.pop_back().unwrap(){
-                                new += o;
-                            },
-                            _ => unreachable!()
-                        }
-                    }
-                    if let Some(t) = tokens.back() {
-                        match t {
-                            /*Token::Prod => {
-                                tokens.pop_back().unwrap();
-                                if let Token::Num(o) = tokens.pop_back().unwrap() {
-                                    new *= o;
-                                }
-                            },*/
-                            Token::Add => {
-                                tokens.pop_back().unwrap();
-                                if let Token::Num(o) = tokens.pop_back().unwrap() {
-                                    new += o;
-                                }
-                            },
-                            _ => {}
-                        }
-                    }
-                    tokens.push_back(Token::Num(new))
-                };
-            }
-            
-        } {
-                tokens.push_back(Token::Num(num))
+fn expression(tokens: &[Token], pos: &mut usize, add_first: bool) -> u128 {
+    let mut product = 1;
+    let mut result = operand(tokens, pos, add_first);
+    while let Some(token) = tokens.get(*pos) {
+        match token {
+            Token::CloseParen => break,
+            Token::Add => {
+                *pos += 1;
+                result += operand(tokens, pos, add_first);
             }
-            } else if s == "*" {
-            tokens.push_back(Token::Prod)
-        } else if s == "+" {
-            tokens.push_back(Token::Add)
-        } else { // its a number
-            let v: u128 = s.parse().unwrap();
-            if tokens.is_empty() {
-                tokens.push_back(Token::Num(v))
-            } else {
-                if let Token::Add = tokens.back().unwrap() {
-                    let operand = tokens.pop_back().unwrap();
-                    let val = tokens.pop_back().unwrap();
-                    if let Token::Num(other) = val {
-                        match operand {
-                            Token::Add => tokens.push_back(Token::Num(v + other)),
-                            _ => unreachable!()
-                        }
-                    }
+            Token::Prod => {
+                *pos += 1;
+                let other = operand(tokens, pos, add_first);
+                if add_first {
+                    // hold the product back until all the additions around it are done
+                    product *= result;
+                    result = other;
                 } else {
-                    tokens.push_back(Token::Num(v));
+                    result *= other;
                 }
             }
+            _ => unreachable!(),
         }
     }
-    if let Token::Num(val) = tokens.pop_back().unwrap() {
-        let mut result = val;
-        while !tokens.is_empty() {
-            let operand = tokens.pop_back().unwrap();
-            let val = tokens.pop_back().unwrap();
-            if let Token::Num(other) = val {
-                match operand {
-                    Token::Prod => result *= other,
-                    Token::Add => result += other,
-                    _ => unreachable!()
-                }
-            }
-        }
-        return result;
-    }
-    0
+    product * result
 }
 
+/// Parses each line of homework into a validated sequence of tokens.
+#[aoc_generator(day18)]
+pub fn parse(input: &str) -> Result<Vec<Vec<Token>>, ParseError> {
+    parse_lines(input, tokenize)
+}
 
+/// The sum of every expression, evaluating operators strictly left to right.
 #[aoc(day18, part1)]
-fn do_the_dumb_math(input: &str) -> u128 {
-    input.lines().map(|l| do_math_from_str(l)).sum()
+pub fn part1(input: &[Vec<Token>]) -> u128 {
+    input.iter().map(|l| expression(l, &mut 0, false)).sum()
 }
 
+/// The sum of every expression, evaluating addition before multiplication.
+#[aoc(day18, part2)]
+pub fn part2(input: &[Vec<Token>]) -> u128 {
+    input.iter().map(|l| expression(l, &mut 0, true)).sum()
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
-   
+
+    fn do_math_from_str(line: &str) -> Result<u128, ParseError> {
+        Ok(expression(&tokenize(line)?, &mut 0, false))
+    }
+
+    fn do_other_math_from_str(line: &str) -> Result<u128, ParseError> {
+        Ok(expression(&tokenize(line)?, &mut 0, true))
+    }
+
     #[test]
-    fn test_do_math(){
-        assert_eq!(do_math_from_str("2 * 3 + (4 * 5)"), 26);
-        assert_eq!(do_math_from_str("5 + (8 * 3 + 9 + 3 * 4 * 3)"), 437);
-        assert_eq!(do_math_from_str("5 * 9 * (7 * 3 * 3 + 9 * 3 + (8 + 6 * 4))"), 12240);
-        assert_eq!(do_math_from_str("((2 + 4 * 9) * (6 + 9 * 8 + 6) + 6) + 2 + 4 * 2"), 13632);
-        assert_eq!(do_math_from_str("1 + (2 * 3) + (4 * (5 + 6))"), 51);
-        assert_eq!(do_math_from_str("1 + 2 * 3 + 4 * 5 + 6"), 71);
-        assert_eq!(do_math_from_str("((5 + 6 + 9 + 6) + 3 + 4 * 6 * 5)"), 990);
-        assert_eq!(do_math_from_str("9 * 3 * ((3 * 6 + 4 + 8) + 9 + 2) * 3"), 3321);
+    fn test_do_math() {
+        assert_eq!(do_math_from_str("2 * 3 + (4 * 5)"), Ok(26));
+        assert_eq!(do_math_from_str("5 + (8 * 3 + 9 + 3 * 4 * 3)"), Ok(437));
+        assert_eq!(
+            do_math_from_str("5 * 9 * (7 * 3 * 3 + 9 * 3 + (8 + 6 * 4))"),
+            Ok(12240)
+        );
+        assert_eq!(
+            do_math_from_str("((2 + 4 * 9) * (6 + 9 * 8 + 6) + 6) + 2 + 4 * 2"),
+            Ok(13632)
+        );
+        assert_eq!(do_math_from_str("1 + (2 * 3) + (4 * (5 + 6))"), Ok(51));
+        assert_eq!(do_math_from_str("1 + 2 * 3 + 4 * 5 + 6"), Ok(71));
+        assert_eq!(
+            do_math_from_str("((5 + 6 + 9 + 6) + 3 + 4 * 6 * 5)"),
+            Ok(990)
+        );
+        assert_eq!(
+            do_math_from_str("9 * 3 * ((3 * 6 + 4 + 8) + 9 + 2) * 3"),
+            Ok(3321)
+        );
     }
 
     #[test]
     fn test_do_other_math() {
-        assert_eq!(do_other_math_from_str("2 * 3 + (4 * 5)"), 46);
-        assert_eq!(do_other_math_from_str("5 + (8 * 3 + 9 + 3 * 4 * 3)"), 1445);
-        assert_eq!(do_other_math_from_str("5 * 9 * (7 * 3 * 3 + 9 * 3 + (8 + 6 * 4))"), 669060);
-        assert_eq!(do_other_math_from_str("((2 + 4 * 9) * (6 + 9 * 8 + 6) + 6) + 2 + 4 * 2"), 23340);
-        assert_eq!(do_other_math_from_str("1 + (2 * 3) + (4 * (5 + 6))"), 51);
-        assert_eq!(do_other_math_from_str("1 + 2 * 3 + 4 * 5 + 6"), 231);
-        //assert_eq!(do_other_math_from_str("((5 + 6 + 9 + 6) + 3 + 4 * 6 * 5)"), 990);
-        //assert_eq!(do_other_math_from_str("9 * 3 * ((3 * 6 + 4 + 8) + 9 + 2) * 3"), 3321);
+        assert_eq!(do_other_math_from_str("2 * 3 + (4 * 5)"), Ok(46));
+        assert_eq!(
+            do_other_math_from_str("5 + (8 * 3 + 9 + 3 * 4 * 3)"),
+            Ok(1445)
+        );
+        assert_eq!(
+            do_other_math_from_str("5 * 9 * (7 * 3 * 3 + 9 * 3 + (8 + 6 * 4))"),
+            Ok(669060)
+        );
+        assert_eq!(
+            do_other_math_from_str("((2 + 4 * 9) * (6 + 9 * 8 + 6) + 6) + 2 + 4 * 2"),
+            Ok(23340)
+        );
+        assert_eq!(
+            do_other_math_from_str("1 + (2 * 3) + (4 * (5 + 6))"),
+            Ok(51)
+        );
+        assert_eq!(do_other_math_from_str("1 + 2 * 3 + 4 * 5 + 6"), Ok(231));
+        //assert_eq!(do_other_math_from_str("((5 + 6 + 9 + 6) + 3 + 4 * 6 * 5)"), Ok(990));
+        //assert_eq!(do_other_math_from_str("9 * 3 * ((3 * 6 + 4 + 8) + 9 + 2) * 3"), Ok(3321));
     }
-    
 }
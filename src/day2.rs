@@ -1,68 +1,502 @@
+use crate::error::{missing, parse_lines, parse_number, ConfigError, ParseError, ParseErrorKind};
+use regex::Regex;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// What a position in a password counts.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum Unit {
+    /// User-perceived characters, so `e` followed by a combining accent is one position.
+    Grapheme,
+    /// Unicode scalar values, as `str::chars` yields them.
+    #[default]
+    Char,
+    /// UTF-8 bytes.
+    Byte,
+}
+
+impl Unit {
+    /// The byte offset at which each position of `password` starts.
+    fn offsets(self, password: &str) -> Vec<usize> {
+        match self {
+            Unit::Grapheme => password.grapheme_indices(true).map(|(i, _)| i).collect(),
+            Unit::Char => password.char_indices().map(|(i, _)| i).collect(),
+            Unit::Byte => (0..password.len()).collect(),
+        }
+    }
+}
+
+/// A policy line such as `1-3 a`: a character and the two numbers that constrain it.
 #[derive(Debug, Eq, PartialEq)]
 pub struct PasswordPolicy {
-    character: char,
-    min: usize,
-    max: usize,
+    /// A single grapheme, which may be several chars such as `e` and a combining accent.
+    pub character: String,
+    pub min: usize,
+    pub max: usize,
 }
 
-impl From<&String> for PasswordPolicy {
-    fn from(s: &String) -> Self {
-        let spaces: Vec<&str> = s.split(" ").collect();
-        let character: char = spaces[1].chars().next().unwrap();
-        let vec: Vec<usize> = spaces[0].split("-").map(|i| i.parse().unwrap()).collect();
-        PasswordPolicy {
-            character,
-            min: vec[0],
-            max: vec[1],
+impl FromStr for PasswordPolicy {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut spaces = s.split(' ');
+        let range = spaces.next().unwrap_or_default();
+        let character = spaces
+            .next()
+            .filter(|c| !c.is_empty())
+            .ok_or_else(|| missing(2, s, "policy character"))?;
+        if character.graphemes(true).count() != 1 {
+            return Err(ParseError::located(
+                2,
+                s,
+                character,
+                ParseErrorKind::UnexpectedToken,
+            ));
         }
+        let mut bounds = range.split('-');
+        let min = parse_number(2, s, bounds.next().unwrap_or_default())?;
+        let max = bounds
+            .next()
+            .ok_or_else(|| missing(2, s, "policy maximum"))
+            .and_then(|m| parse_number(2, s, m))?;
+        if min > max {
+            return Err(ParseError::located(
+                2,
+                s,
+                range,
+                ParseErrorKind::Invalid("range, minimum above maximum"),
+            ));
+        }
+        Ok(PasswordPolicy {
+            character: String::from(character),
+            min,
+            max,
+        })
+    }
+}
+
+impl fmt::Display for PasswordPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{} {}", self.min, self.max, self.character)
     }
 }
 
 impl PasswordPolicy {
+    /// The (1-based) positions of `password`, counted in `unit`, at which the character
+    /// starts. With [`Unit::Grapheme`] the character must be the whole grapheme.
+    pub fn positions(&self, password: &str, unit: Unit) -> Vec<usize> {
+        let offsets = unit.offsets(password);
+        let mut ends = offsets.iter().skip(1).copied().chain(Some(password.len()));
+        offsets
+            .iter()
+            .enumerate()
+            .filter(|&(_, &start)| {
+                let end = ends.next().unwrap();
+                let rest = &password.as_bytes()[start..];
+                match unit {
+                    Unit::Grapheme => password[start..end] == self.character,
+                    Unit::Char | Unit::Byte => rest.starts_with(self.character.as_bytes()),
+                }
+            })
+            .map(|(i, _)| i + 1)
+            .collect()
+    }
+
+    /// Whether `password` contains the character between `min` and `max` times.
     pub fn satisfied_1(&self, password: &str) -> bool {
-        let ct = password.chars().filter(|c| *c == self.character).count();
-        (self.min - 1) < ct && ct < (self.max + 1)
+        self.count_satisfied(password, Unit::Char)
     }
 
+    /// Whether exactly one of the (1-based) positions `min` and `max` holds the character. When
+    /// `min` and `max` are the same position, that position holding it is enough.
     pub fn satisfied_2(&self, password: &str) -> bool {
-        let mut ans = false;
-        for (i, c) in password.chars().enumerate() {
-            if (i + 1) == self.min || (i + 1) == self.max {
-                ans ^= self.character == c;
-            }
+        self.positions_satisfied(password, Unit::Char)
+    }
+
+    /// [`PasswordPolicy::satisfied_1`] counting occurrences in `unit`.
+    pub fn count_satisfied(&self, password: &str, unit: Unit) -> bool {
+        let ct = self.positions(password, unit).len();
+        self.min <= ct && ct <= self.max
+    }
+
+    /// [`PasswordPolicy::satisfied_2`] with positions counted in `unit`. Position 0 never holds
+    /// the character.
+    pub fn positions_satisfied(&self, password: &str, unit: Unit) -> bool {
+        let positions = self.positions(password, unit);
+        if self.min == self.max {
+            return positions.contains(&self.min);
         }
-        ans
+        positions.contains(&self.min) != positions.contains(&self.max)
     }
 }
 
-#[aoc_generator(day2)]
-fn input_to_vec(input: &str) -> Vec<(PasswordPolicy, String)> {
-    input
-        .lines()
-        .map(|i| {
-            let splt = i
-                .split(": ")
-                .map(|s| String::from(s))
-                .collect::<Vec<String>>();
-            (PasswordPolicy::from(&splt[0]), splt[1].to_string())
+/// A requirement a password must meet, given the policy written next to it in the database.
+/// Rules that don't depend on the line, such as [`MinDistinct`], ignore the policy.
+pub trait PasswordRule {
+    /// Why `password` breaks the rule, or `None` if it satisfies it.
+    fn violation(&self, policy: &PasswordPolicy, password: &str) -> Option<String>;
+
+    /// What the rule requires of passwords under `policy`.
+    fn describe(&self, policy: &PasswordPolicy) -> String;
+
+    fn is_satisfied(&self, policy: &PasswordPolicy, password: &str) -> bool {
+        self.violation(policy, password).is_none()
+    }
+}
+
+/// The policy character appears between `min` and `max` times.
+#[derive(Default)]
+pub struct CountInRange(pub Unit);
+
+impl PasswordRule for CountInRange {
+    fn violation(&self, policy: &PasswordPolicy, password: &str) -> Option<String> {
+        if policy.count_satisfied(password, self.0) {
+            return Option::None;
+        }
+        let ct = policy.positions(password, self.0).len();
+        Option::Some(format!(
+            "character '{}' appears {} times, allowed {}-{}",
+            policy.character, ct, policy.min, policy.max
+        ))
+    }
+
+    fn describe(&self, policy: &PasswordPolicy) -> String {
+        format!(
+            "character '{}' appears {}-{} times",
+            policy.character, policy.min, policy.max
+        )
+    }
+}
+
+/// Exactly one of the positions `min` and `max` holds the policy character.
+#[derive(Default)]
+pub struct PositionalXor(pub Unit);
+
+impl PasswordRule for PositionalXor {
+    fn violation(&self, policy: &PasswordPolicy, password: &str) -> Option<String> {
+        if policy.positions_satisfied(password, self.0) {
+            return Option::None;
+        }
+        let positions = policy.positions(password, self.0);
+        Option::Some(if positions.contains(&policy.min) {
+            format!(
+                "positions {} and {} both match '{}'",
+                policy.min, policy.max, policy.character
+            )
+        } else {
+            format!(
+                "neither position {} nor {} matches '{}'",
+                policy.min, policy.max, policy.character
+            )
         })
+    }
+
+    fn describe(&self, policy: &PasswordPolicy) -> String {
+        format!(
+            "exactly one of positions {} and {} is '{}'",
+            policy.min, policy.max, policy.character
+        )
+    }
+}
+
+/// The password matches a regular expression.
+pub struct Matches(pub Regex);
+
+impl PasswordRule for Matches {
+    fn violation(&self, policy: &PasswordPolicy, password: &str) -> Option<String> {
+        if self.0.is_match(password) {
+            return Option::None;
+        }
+        Option::Some(format!("does not match: {}", self.describe(policy)))
+    }
+
+    fn describe(&self, _: &PasswordPolicy) -> String {
+        format!("matches /{}/", self.0)
+    }
+}
+
+/// The password has at least this many different characters.
+pub struct MinDistinct(pub usize);
+
+impl PasswordRule for MinDistinct {
+    fn violation(&self, _: &PasswordPolicy, password: &str) -> Option<String> {
+        let distinct = password.chars().collect::<HashSet<char>>().len();
+        if distinct >= self.0 {
+            return Option::None;
+        }
+        Option::Some(format!(
+            "has {} distinct characters, needs {}",
+            distinct, self.0
+        ))
+    }
+
+    fn describe(&self, _: &PasswordPolicy) -> String {
+        format!("has at least {} distinct characters", self.0)
+    }
+}
+
+/// The password contains none of these substrings.
+pub struct Forbidden(pub Vec<String>);
+
+impl PasswordRule for Forbidden {
+    fn violation(&self, _: &PasswordPolicy, password: &str) -> Option<String> {
+        let found: Vec<String> = self
+            .0
+            .iter()
+            .filter(|f| password.contains(f.as_str()))
+            .map(|f| format!("\"{}\"", f))
+            .collect();
+        if found.is_empty() {
+            return Option::None;
+        }
+        Option::Some(format!("contains forbidden {}", found.join(", ")))
+    }
+
+    fn describe(&self, _: &PasswordPolicy) -> String {
+        let words: Vec<String> = self.0.iter().map(|f| format!("\"{}\"", f)).collect();
+        format!("contains none of {}", words.join(", "))
+    }
+}
+
+fn describe_all(rules: &[Box<dyn PasswordRule>], policy: &PasswordPolicy, join: &str) -> String {
+    let rules: Vec<String> = rules.iter().map(|r| r.describe(policy)).collect();
+    format!("({})", rules.join(join))
+}
+
+/// Every rule is satisfied.
+pub struct All(pub Vec<Box<dyn PasswordRule>>);
+
+impl PasswordRule for All {
+    fn violation(&self, policy: &PasswordPolicy, password: &str) -> Option<String> {
+        let violations: Vec<String> = self
+            .0
+            .iter()
+            .filter_map(|r| r.violation(policy, password))
+            .collect();
+        if violations.is_empty() {
+            return Option::None;
+        }
+        Option::Some(violations.join("; "))
+    }
+
+    fn describe(&self, policy: &PasswordPolicy) -> String {
+        describe_all(&self.0, policy, " and ")
+    }
+}
+
+/// At least one rule is satisfied.
+pub struct Any(pub Vec<Box<dyn PasswordRule>>);
+
+impl PasswordRule for Any {
+    fn violation(&self, policy: &PasswordPolicy, password: &str) -> Option<String> {
+        let mut violations = vec![];
+        for r in &self.0 {
+            violations.push(r.violation(policy, password)?);
+        }
+        Option::Some(format!("none of: {}", violations.join("; ")))
+    }
+
+    fn describe(&self, policy: &PasswordPolicy) -> String {
+        describe_all(&self.0, policy, " or ")
+    }
+}
+
+/// The rule is not satisfied.
+pub struct Not(pub Box<dyn PasswordRule>);
+
+impl PasswordRule for Not {
+    fn violation(&self, policy: &PasswordPolicy, password: &str) -> Option<String> {
+        if self.0.is_satisfied(policy, password) {
+            Option::Some(format!("should not satisfy: {}", self.0.describe(policy)))
+        } else {
+            Option::None
+        }
+    }
+
+    fn describe(&self, policy: &PasswordPolicy) -> String {
+        format!("not {}", self.0.describe(policy))
+    }
+}
+
+fn invalid(message: String) -> ConfigError {
+    ConfigError::new("password rule", message)
+}
+
+fn rules_from_value(value: &Value) -> Result<Vec<Box<dyn PasswordRule>>, ConfigError> {
+    value
+        .as_array()
+        .ok_or_else(|| invalid(format!("expected a list of rules, found `{}`", value)))?
+        .iter()
+        .map(rule_from_value)
         .collect()
 }
 
-#[aoc(day2, part1)]
-fn valid_password_count(input: &Vec<(PasswordPolicy, String)>) -> usize {
+fn rule_from_value(value: &Value) -> Result<Box<dyn PasswordRule>, ConfigError> {
+    if let Some(name) = value.as_str() {
+        return match name {
+            "count" => Ok(Box::new(CountInRange::default())),
+            "positions" => Ok(Box::new(PositionalXor::default())),
+            _ => Err(invalid(format!("unknown rule `{}`", name))),
+        };
+    }
+    let (name, arg) = match value.as_object() {
+        Some(o) if o.len() == 1 => o.iter().next().unwrap(),
+        _ => return Err(invalid(format!("expected a rule, found `{}`", value))),
+    };
+    let bad_argument = || invalid(format!("bad argument for `{}`: `{}`", name, arg));
+    let unit = || match arg.as_str() {
+        Some("grapheme") => Ok(Unit::Grapheme),
+        Some("char") => Ok(Unit::Char),
+        Some("byte") => Ok(Unit::Byte),
+        _ => Err(bad_argument()),
+    };
+    match name.as_str() {
+        "count" => Ok(Box::new(CountInRange(unit()?))),
+        "positions" => Ok(Box::new(PositionalXor(unit()?))),
+        "regex" => {
+            let pattern = arg.as_str().ok_or_else(bad_argument)?;
+            let regex = Regex::new(pattern).map_err(|e| invalid(e.to_string()))?;
+            Ok(Box::new(Matches(regex)))
+        }
+        "min_distinct" => {
+            let n = arg.as_u64().ok_or_else(bad_argument)?;
+            Ok(Box::new(MinDistinct(n as usize)))
+        }
+        "forbidden" => {
+            let words = arg
+                .as_array()
+                .and_then(|a| {
+                    a.iter()
+                        .map(|w| w.as_str().map(String::from))
+                        .collect::<Option<Vec<String>>>()
+                })
+                .ok_or_else(bad_argument)?;
+            Ok(Box::new(Forbidden(words)))
+        }
+        "all" => Ok(Box::new(All(rules_from_value(arg)?))),
+        "any" => Ok(Box::new(Any(rules_from_value(arg)?))),
+        "not" => Ok(Box::new(Not(rule_from_value(arg)?))),
+        _ => Err(invalid(format!("unknown rule `{}`", name))),
+    }
+}
+
+/// Reads a rule from a JSON policy file. `"count"` and `"positions"` are the two puzzle rules,
+/// counting chars; `{"count": unit}` and `{"positions": unit}` count `"grapheme"`, `"char"` or
+/// `"byte"` instead. The others are single-key objects:
+///
+/// ```json
+/// {"all": [
+///     {"count": "grapheme"},
+///     {"regex": "^[a-z]+$"},
+///     {"min_distinct": 4},
+///     {"forbidden": ["password", "1234"]},
+///     {"any": ["positions", {"not": {"regex": "z"}}]}
+/// ]}
+/// ```
+pub fn rule_from_json(text: &str) -> Result<Box<dyn PasswordRule>, ConfigError> {
+    let value: Value = serde_json::from_str(text).map_err(|e| invalid(e.to_string()))?;
+    rule_from_value(&value)
+}
+
+/// How many passwords satisfy `rule`.
+pub fn count_valid(input: &[(PasswordPolicy, String)], rule: &dyn PasswordRule) -> usize {
     input
         .iter()
-        .filter(|(policy, password)| policy.satisfied_1(password.as_str()))
+        .filter(|(policy, password)| rule.is_satisfied(policy, password))
         .count()
 }
 
-#[aoc(day2, part2)]
-fn valid_password_count2(input: &Vec<(PasswordPolicy, String)>) -> usize {
+/// The outcome of checking one database entry against a rule.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Diagnostic {
+    /// The entry's line in the database, 1-based.
+    pub line: usize,
+    pub policy: String,
+    pub password: String,
+    /// Why the password breaks the rule, or `None` if it is valid.
+    pub violation: Option<String>,
+}
+
+/// Checks every entry against `rule`, explaining each failure.
+pub fn diagnose(input: &[(PasswordPolicy, String)], rule: &dyn PasswordRule) -> Vec<Diagnostic> {
     input
         .iter()
-        .filter(|(policy, password)| policy.satisfied_2(password.as_str()))
-        .count()
+        .enumerate()
+        .map(|(i, (policy, password))| Diagnostic {
+            line: i + 1,
+            policy: policy.to_string(),
+            password: password.clone(),
+            violation: rule.violation(policy, password),
+        })
+        .collect()
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        String::from(field)
+    }
+}
+
+/// Writes `diagnostics` as CSV with a `line,policy,password,valid,violation` header.
+pub fn diagnostics_to_csv(diagnostics: &[Diagnostic]) -> String {
+    let mut out = String::from("line,policy,password,valid,violation\n");
+    for d in diagnostics {
+        out.push_str(&format!(
+            "{},{},{},{},{}\n",
+            d.line,
+            csv_field(&d.policy),
+            csv_field(&d.password),
+            d.violation.is_none(),
+            csv_field(d.violation.as_deref().unwrap_or_default())
+        ));
+    }
+    out
+}
+
+/// Writes `diagnostics` as a JSON array of objects, with `violation` null for valid entries.
+pub fn diagnostics_to_json(diagnostics: &[Diagnostic]) -> Value {
+    Value::Array(
+        diagnostics
+            .iter()
+            .map(|d| {
+                json!({
+                    "line": d.line,
+                    "policy": d.policy,
+                    "password": d.password,
+                    "valid": d.violation.is_none(),
+                    "violation": d.violation,
+                })
+            })
+            .collect(),
+    )
+}
+
+/// Parses the password database into `(policy, password)` pairs.
+#[aoc_generator(day2)]
+pub fn parse(input: &str) -> Result<Vec<(PasswordPolicy, String)>, ParseError> {
+    parse_lines(input, |l| {
+        let mut splt = l.splitn(2, ": ");
+        let policy = splt.next().unwrap_or_default().parse()?;
+        let password = splt.next().ok_or_else(|| missing(2, l, "password"))?;
+        Ok((policy, password.to_string()))
+    })
+}
+
+/// How many passwords are valid under the character count interpretation of their policy.
+#[aoc(day2, part1)]
+pub fn part1(input: &[(PasswordPolicy, String)]) -> usize {
+    count_valid(input, &CountInRange::default())
+}
+
+/// How many passwords are valid under the positional interpretation of their policy.
+#[aoc(day2, part2)]
+pub fn part2(input: &[(PasswordPolicy, String)]) -> usize {
+    count_valid(input, &PositionalXor::default())
 }
 
 #[cfg(test)]
@@ -72,14 +506,153 @@ mod tests {
     #[test]
     fn valid_policy() {
         let input = "1-3 a: abcde \n1-3 b: cdefg \n2-9 c: ccccccccc\n";
-        let input = input_to_vec(input);
-        assert_eq!(valid_password_count(&input), 2)
+        let input = parse(input).unwrap();
+        assert_eq!(part1(&input), 2)
     }
 
     #[test]
     fn valid_policy_2() {
         let input = "1-3 a: abcde \n1-3 b: cdefg \n2-9 c: ccccccccc\n";
-        let input = input_to_vec(input);
-        assert_eq!(valid_password_count2(&input), 1)
+        let input = parse(input).unwrap();
+        assert_eq!(part2(&input), 1);
+        let input = parse("1-1 e: eat\n2-2 e: eat\n").unwrap();
+        assert_eq!(part2(&input), 1);
+        assert!(input[0].0.satisfied_2("eat"));
+        assert!(!input[1].0.satisfied_2("eat"));
+    }
+
+    #[test]
+    fn invalid_policy() {
+        let e = parse("1-3 a: abcde\n1-x b: cdefg").unwrap_err();
+        assert_eq!((e.line, e.column, e.text.as_str()), (2, 3, "x"));
+    }
+
+    #[test]
+    fn rules() {
+        let input =
+            parse("1-3 a: abcde\n1-3 b: cdefg\n2-9 c: ccccccccc\n1-2 p: password1").unwrap();
+        let count = |json: &str| count_valid(&input, &*rule_from_json(json).unwrap());
+        assert_eq!(count(r#""count""#), 3);
+        assert_eq!(count(r#"{"regex": "^[a-z]+$"}"#), 3);
+        assert_eq!(count(r#"{"min_distinct": 5}"#), 3);
+        assert_eq!(count(r#"{"forbidden": ["password", "ccc"]}"#), 2);
+        assert_eq!(count(r#"{"all": ["count", {"min_distinct": 2}]}"#), 2);
+        assert_eq!(count(r#"{"any": ["positions", {"regex": "g$"}]}"#), 3);
+        assert_eq!(count(r#"{"not": "positions"}"#), 2);
+        assert_eq!(count(r#"{"all": []}"#), 4);
+    }
+
+    #[test]
+    fn invalid_rules() {
+        for json in &[
+            r#""length""#,
+            r#"{"regex": "("}"#,
+            r#"{"min_distinct": "a"}"#,
+            r#"{"forbidden": [1]}"#,
+            r#"{"all": "count"}"#,
+            r#"{"not": "count", "all": []}"#,
+            "[",
+        ] {
+            assert!(rule_from_json(json).is_err(), "{}", json);
+        }
+    }
+
+    #[test]
+    fn diagnostics() {
+        let input = parse("1-3 a: abcde\n1-3 b: cdefg\n2-9 c: ccccccccc\n1-3 a: aaaaa").unwrap();
+        let violations: Vec<Option<String>> = diagnose(&input, &CountInRange::default())
+            .into_iter()
+            .map(|d| d.violation)
+            .collect();
+        assert_eq!(
+            violations,
+            vec![
+                None,
+                Some(String::from("character 'b' appears 0 times, allowed 1-3")),
+                None,
+                Some(String::from("character 'a' appears 5 times, allowed 1-3")),
+            ]
+        );
+
+        let diagnostics = diagnose(&input, &PositionalXor::default());
+        assert_eq!(
+            diagnostics[2].violation.as_deref(),
+            Some("positions 2 and 9 both match 'c'")
+        );
+        assert_eq!(
+            diagnostics[1].violation.as_deref(),
+            Some("neither position 1 nor 3 matches 'b'")
+        );
+
+        let rule =
+            rule_from_json(r#"{"all": [{"forbidden": ["aa", "cc"]}, {"not": "count"}]}"#).unwrap();
+        assert_eq!(
+            rule.violation(&input[2].0, &input[2].1).as_deref(),
+            Some("contains forbidden \"cc\"; should not satisfy: character 'c' appears 2-9 times")
+        );
+    }
+
+    #[test]
+    fn diagnostics_export() {
+        let input = parse("1-3 a: a,\"b\n1-3 b: b").unwrap();
+        let diagnostics = diagnose(&input, &MinDistinct(2));
+        assert_eq!(
+            diagnostics_to_csv(&diagnostics),
+            "line,policy,password,valid,violation\n\
+             1,1-3 a,\"a,\"\"b\",true,\n\
+             2,1-3 b,b,false,\"has 1 distinct characters, needs 2\"\n"
+        );
+        let json = diagnostics_to_json(&diagnostics);
+        assert_eq!(json[0]["violation"], Value::Null);
+        assert_eq!(json[1]["line"], 2);
+        assert_eq!(json[1]["valid"], false);
+    }
+
+    #[test]
+    fn policy_validation() {
+        let e = parse("1-3 a: abcde\n5-3 b: cdefg").unwrap_err();
+        assert_eq!((e.line, e.column, e.text.as_str()), (2, 1, "5-3"));
+        assert_eq!(
+            e.kind,
+            ParseErrorKind::Invalid("range, minimum above maximum")
+        );
+        let e = parse("1-3 ab: abcde").unwrap_err();
+        assert_eq!((e.column, e.kind), (5, ParseErrorKind::UnexpectedToken));
+        assert!(parse("1-3 : abcde").is_err());
+        assert!(parse("1-3 e\u{301}: abcde").is_ok());
+    }
+
+    #[test]
+    fn zero_minimum() {
+        let input = parse("0-1 a: bcd\n0-1 a: aa\n0-2 b: abc\n0-2 b: bac").unwrap();
+        assert_eq!(part1(&input), 3);
+        assert_eq!(part2(&input), 2);
+    }
+
+    #[test]
+    fn units() {
+        // "e" then a combining acute accent, so one grapheme of two chars and three bytes
+        let input =
+            parse("1-2 \u{e9}: \u{e9}e\u{301}x\n2-4 x: e\u{301}x\n1-1 e: e\u{301}").unwrap();
+        let (policy, password) = &input[0];
+        assert_eq!(policy.positions(password, Unit::Char), vec![1]);
+        assert_eq!(policy.positions(password, Unit::Byte), vec![1]);
+        let (policy, password) = &input[1];
+        assert_eq!(policy.positions(password, Unit::Grapheme), vec![2]);
+        assert_eq!(policy.positions(password, Unit::Char), vec![3]);
+        assert_eq!(policy.positions(password, Unit::Byte), vec![4]);
+        let (policy, password) = &input[2];
+        assert_eq!(
+            policy.positions(password, Unit::Grapheme),
+            Vec::<usize>::new()
+        );
+        assert_eq!(policy.positions(password, Unit::Char), vec![1]);
+
+        let count = |json: &str| count_valid(&input, &*rule_from_json(json).unwrap());
+        assert_eq!(count(r#"{"positions": "grapheme"}"#), 2);
+        assert_eq!(count(r#"{"positions": "char"}"#), 2);
+        assert_eq!(count(r#"{"positions": "byte"}"#), 3);
+        assert_eq!(count(r#"{"count": "grapheme"}"#), 1);
+        assert!(rule_from_json(r#"{"count": "word"}"#).is_err());
     }
 }
@@ -1,77 +1,301 @@
-enum Entry {
-    Tree,
+use crate::error::{missing, parse_number, ParseError};
+use crate::grid::{BitGrid, Grid};
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+/// A direction to travel in: `dx` right (left if negative) and `dy` down at each step.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Slope {
+    pub dx: isize,
+    pub dy: usize,
+}
+
+impl FromStr for Slope {
+    type Err = ParseError;
+
+    /// Parses `dx,dy`, e.g. `3,1` or `-1,2`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(2, ',');
+        let dx = parse_number(3, s, parts.next().unwrap_or_default().trim())?;
+        let dy = parts
+            .next()
+            .ok_or_else(|| missing(3, s, "slope down"))
+            .and_then(|dy| parse_number(3, s, dy.trim()))?;
+        Ok(Slope { dx, dy })
+    }
+}
+
+impl fmt::Display for Slope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{}", self.dx, self.dy)
+    }
+}
+
+/// Parses a list of slopes separated by whitespace or `;`, e.g. `1,1 3,1 -2,1`.
+pub fn parse_slopes(s: &str) -> Result<Vec<Slope>, ParseError> {
+    s.split(|c: char| c.is_whitespace() || c == ';')
+        .filter(|slope| !slope.is_empty())
+        .map(|slope| slope.parse().map_err(|e: ParseError| e.within(s, slope)))
+        .collect()
+}
+
+/// The toboggan map, whose pattern repeats endlessly to the left and right.
+#[derive(Debug)]
+pub struct TobogganMap {
+    /// Where the trees are in one copy of the pattern.
+    pub trees: BitGrid,
+}
+
+impl TobogganMap {
+    /// The width of the repeating pattern.
+    pub fn width(&self) -> usize {
+        self.trees.width()
+    }
+
+    pub fn height(&self) -> usize {
+        self.trees.height()
+    }
+
+    /// Whether there is a tree at column `x` (wrapping in both directions) of row `y`, or `None`
+    /// below the bottom of the map.
+    pub fn is_tree(&self, x: isize, y: usize) -> Option<bool> {
+        if y < self.height() {
+            Some(self.trees.get_wrapping(x, y as isize))
+        } else {
+            None
+        }
+    }
+
+    /// The positions visited going `dx` right and `dy` down at each step from the top left,
+    /// starting with the top left itself. A `dy` of 0 never leaves the top row, so it only
+    /// visits the start.
+    pub fn path(&self, dx: isize, dy: usize) -> Vec<(isize, usize)> {
+        if self.height() == 0 {
+            return vec![];
+        }
+        let steps = (self.height() - 1).checked_div(dy).unwrap_or(0);
+        (0..=steps)
+            .map(|step| (dx * step as isize, dy * step))
+            .collect()
+    }
+
+    /// How many trees are hit going `dx` right and `dy` down at each step from the top left.
+    pub fn trees_on_slope(&self, dx: isize, dy: usize) -> usize {
+        self.path(dx, dy)
+            .into_iter()
+            .skip(1)
+            .filter(|&(x, y)| self.is_tree(x, y) == Some(true))
+            .count()
+    }
+
+    /// How many trees are hit on each of `slopes`.
+    pub fn trees_on_slopes(&self, slopes: &[Slope]) -> Vec<usize> {
+        slopes
+            .iter()
+            .map(|s| self.trees_on_slope(s.dx, s.dy))
+            .collect()
+    }
+
+    fn slopes_within(
+        dx: RangeInclusive<isize>,
+        dy: RangeInclusive<usize>,
+    ) -> impl Iterator<Item = Slope> {
+        dx.flat_map(move |dx| {
+            dy.clone()
+                .filter(|&dy| dy > 0)
+                .map(move |dy| Slope { dx, dy })
+        })
+    }
+
+    /// The slope within the bounds hitting the fewest trees, and how many it hits. Ties go to
+    /// the smallest `dx`, then the smallest `dy`.
+    pub fn fewest_trees(
+        &self,
+        dx: RangeInclusive<isize>,
+        dy: RangeInclusive<usize>,
+    ) -> Option<(Slope, usize)> {
+        TobogganMap::slopes_within(dx, dy)
+            .map(|s| (s, self.trees_on_slope(s.dx, s.dy)))
+            .min_by_key(|&(_, trees)| trees)
+    }
+
+    /// The slope within the bounds hitting the most trees, and how many it hits. Ties go to
+    /// the smallest `dx`, then the smallest `dy`.
+    pub fn most_trees(
+        &self,
+        dx: RangeInclusive<isize>,
+        dy: RangeInclusive<usize>,
+    ) -> Option<(Slope, usize)> {
+        TobogganMap::slopes_within(dx, dy)
+            .map(|s| (s, self.trees_on_slope(s.dx, s.dy)))
+            .fold(
+                None,
+                |best: Option<(Slope, usize)>, (s, trees)| match best {
+                    Some((_, most)) if most >= trees => best,
+                    _ => Some((s, trees)),
+                },
+            )
+    }
+}
+
+/// What a rendered square of the map shows.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Cell {
     Snow,
+    Tree,
+    /// Open snow the toboggan passed over, `O` in the puzzle.
+    Passed,
+    /// A tree the toboggan hit, `X` in the puzzle.
+    Hit,
 }
 
-impl From<&str> for Entry {
-    fn from(s: &str) -> Self {
-        match s {
-            "." => Entry::Snow,
-            "#" => Entry::Tree,
-            _ => panic!(format!("unexpected string {}", s)),
+impl Cell {
+    fn symbol(self) -> char {
+        match self {
+            Cell::Snow => '.',
+            Cell::Tree => '#',
+            Cell::Passed => 'O',
+            Cell::Hit => 'X',
+        }
+    }
+
+    fn color(self) -> [u8; 3] {
+        match self {
+            Cell::Snow => [255, 255, 255],
+            Cell::Tree => [34, 139, 34],
+            Cell::Passed => [30, 144, 255],
+            Cell::Hit => [220, 20, 60],
         }
     }
 }
 
-#[aoc_generator(day3)]
-fn input_to_vec(input: &str) -> Vec<Vec<Entry>> {
-    input
-        .lines()
-        .map(|i| {
-            let splt = i
-                .split("")
-                .filter(|s| !s.is_empty())
-                .map(|s| Entry::from(s))
-                .collect();
-            splt
-        })
-        .collect()
+/// The map with a slope's path drawn over it, tiled horizontally as far as the path goes.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PathRender {
+    pub cells: Grid<Cell>,
 }
 
-fn tree_count_for_steps(input: &Vec<Vec<Entry>>, x: usize, y: usize) -> usize {
-    let mut ct = 0;
-    let mut right = 0;
-    for r in 1..input.len() {
-        if r % y > 0 {
-            continue;
+impl PathRender {
+    /// The map as text, the way the puzzle draws it.
+    pub fn to_text(&self) -> String {
+        self.cells
+            .rows()
+            .map(|row| row.iter().map(|c| c.symbol()).collect::<String>())
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    /// A binary PPM image with each square drawn `scale` pixels wide.
+    pub fn to_ppm(&self, scale: usize) -> Vec<u8> {
+        let (width, height) = (self.cells.width(), self.cells.height());
+        let mut out = format!("P6\n{} {}\n255\n", width * scale, height * scale).into_bytes();
+        for row in self.cells.rows() {
+            for _ in 0..scale {
+                for cell in row {
+                    for _ in 0..scale {
+                        out.extend_from_slice(&cell.color());
+                    }
+                }
+            }
         }
-        let row = &input[r];
-        right += x;
-        right %= row.len();
-        if let Entry::Tree = row[right] {
-            ct += 1;
+        out
+    }
+
+    /// An SVG image with each square drawn `scale` units wide.
+    pub fn to_svg(&self, scale: usize) -> String {
+        let (width, height) = (self.cells.width(), self.cells.height());
+        let mut out = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\">\n",
+            width * scale,
+            height * scale
+        );
+        for ((x, y), cell) in self.cells.iter() {
+            let [r, g, b] = cell.color();
+            out.push_str(&format!(
+                "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"rgb({},{},{})\"/>\n",
+                x * scale,
+                y * scale,
+                scale,
+                scale,
+                r,
+                g,
+                b
+            ));
         }
+        out.push_str("</svg>\n");
+        out
     }
-    ct
 }
 
-#[aoc(day3, part1)]
-fn tree_count(input: &Vec<Vec<Entry>>) -> usize {
-    let mut ct = 0;
-    let mut x = 0;
-    for r in 1..input.len() {
-        let row = &input[r];
-        x += 3;
-        x %= row.len();
-        if let Entry::Tree = row[x] {
-            ct += 1;
+impl TobogganMap {
+    /// Draws the path going `dx` right and `dy` down over the map, marking every square after
+    /// the start as passed or hit. The pattern is repeated as many times as the path needs,
+    /// to the left when `dx` is negative.
+    pub fn render(&self, dx: isize, dy: usize) -> PathRender {
+        let width = self.width() as isize;
+        let path = self.path(dx, dy);
+        let min_x = path.iter().map(|&(x, _)| x).min().unwrap_or(0);
+        let max_x = path.iter().map(|&(x, _)| x).max().unwrap_or(0);
+        let first = min_x.div_euclid(width.max(1)) * width;
+        let last = (max_x.div_euclid(width.max(1)) + 1) * width;
+        let mut cells = Grid::new((last - first) as usize, self.height(), Cell::Snow);
+        for y in 0..self.height() {
+            for x in first..last {
+                if self.is_tree(x, y) == Some(true) {
+                    cells[((x - first) as usize, y)] = Cell::Tree;
+                }
+            }
         }
+        for &(x, y) in path.iter().skip(1) {
+            let cell = &mut cells[((x - first) as usize, y)];
+            *cell = match cell {
+                Cell::Tree => Cell::Hit,
+                _ => Cell::Passed,
+            };
+        }
+        PathRender { cells }
     }
-    ct
 }
 
+/// Parses the map, `#` for a tree and `.` for open snow; the pattern repeats to the right.
+#[aoc_generator(day3)]
+pub fn parse(input: &str) -> Result<TobogganMap, ParseError> {
+    Ok(TobogganMap {
+        trees: BitGrid::parse(3, input)?,
+    })
+}
+
+/// How many trees are hit going `x` right and `y` down at each step from the top left.
+pub fn tree_count_for_steps(input: &TobogganMap, x: usize, y: usize) -> usize {
+    input.trees_on_slope(x as isize, y)
+}
+
+/// The slopes multiplied together in part 2.
+pub const SLOPES: [Slope; 5] = [
+    Slope { dx: 1, dy: 1 },
+    Slope { dx: 3, dy: 1 },
+    Slope { dx: 5, dy: 1 },
+    Slope { dx: 7, dy: 1 },
+    Slope { dx: 1, dy: 2 },
+];
+
+/// How many trees are hit on the slope right 3, down 1.
+#[aoc(day3, part1)]
+pub fn part1(input: &TobogganMap) -> usize {
+    input.trees_on_slope(3, 1)
+}
+
+/// The product of the trees hit on each of the five puzzle slopes.
 #[aoc(day3, part2)]
-fn tree_count_for_all_paths(input: &Vec<Vec<Entry>>) -> usize {
-    let mut ct = 1;
-    for (x, y) in vec![(1, 1), (3, 1), (5, 1), (7, 1), (1, 2)].into_iter() {
-        ct *= tree_count_for_steps(input, x, y);
-    }
-    ct
+pub fn part2(input: &TobogganMap) -> usize {
+    input.trees_on_slopes(&SLOPES).into_iter().product()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::ParseErrorKind;
 
     const TEST_INPUT: &str = "..##.......
 #...#...#..
@@ -87,19 +311,119 @@ mod tests {
 
     #[test]
     fn test_tree_count() {
-        let input = input_to_vec(TEST_INPUT);
-        assert_eq!(tree_count(&input), 7);
+        let input = parse(TEST_INPUT).unwrap();
+        assert_eq!(part1(&input), 7);
     }
 
     #[test]
     fn test_tree_count_for_steps() {
-        let input = input_to_vec(TEST_INPUT);
+        let input = parse(TEST_INPUT).unwrap();
         assert_eq!(tree_count_for_steps(&input, 3, 1), 7);
     }
 
     #[test]
     fn test_tree_count_all_paths() {
-        let input = input_to_vec(TEST_INPUT);
-        assert_eq!(tree_count_for_all_paths(&input), 336);
+        let input = parse(TEST_INPUT).unwrap();
+        assert_eq!(part2(&input), 336);
+    }
+
+    #[test]
+    fn test_bad_entry() {
+        let e = parse("..#\n.o.").unwrap_err();
+        assert_eq!((e.line, e.column, e.text.as_str()), (2, 2, "o"));
+    }
+
+    #[test]
+    fn test_trees_on_slope() {
+        let map = parse(TEST_INPUT).unwrap();
+        assert_eq!(map.trees_on_slope(3, 1), 7);
+        assert_eq!(map.trees_on_slope(1, 2), 2);
+        assert_eq!(map.trees_on_slope(0, 0), 0);
+        // the pattern is 11 wide, so going 10 left lands on the same columns as 1 right
+        assert_eq!(map.trees_on_slope(-10, 1), map.trees_on_slope(1, 1));
+        assert_eq!(map.trees_on_slope(-1, 1), 5);
+        assert_eq!(map.is_tree(-9, 0), Some(true));
+        assert_eq!(map.is_tree(0, map.height()), None);
+    }
+
+    #[test]
+    fn test_slopes() {
+        let map = parse(TEST_INPUT).unwrap();
+        let slopes = parse_slopes("1,1 3,1\n5,1; 7,1 1,2").unwrap();
+        assert_eq!(slopes.to_vec(), SLOPES.to_vec());
+        assert_eq!(map.trees_on_slopes(&slopes), vec![2, 7, 3, 4, 2]);
+        let e = parse_slopes("3,1 x,1").unwrap_err();
+        assert_eq!((e.column, e.text.as_str()), (5, "x"));
+        assert!(parse_slopes("3").is_err());
+    }
+
+    #[test]
+    fn test_search() {
+        let map = parse(TEST_INPUT).unwrap();
+        assert_eq!(
+            map.most_trees(1..=7, 1..=1),
+            Some((Slope { dx: 3, dy: 1 }, 7))
+        );
+        assert_eq!(
+            map.fewest_trees(-3..=3, 1..=2),
+            Some((Slope { dx: -3, dy: 2 }, 1))
+        );
+        assert_eq!(map.fewest_trees(1..=3, 0..=0), None);
+    }
+
+    #[test]
+    fn test_ragged_rows() {
+        let e = parse("..#\n.#\n").unwrap_err();
+        assert_eq!((e.line, e.kind), (2, ParseErrorKind::Invalid("row width")));
+        assert!(parse("\n\n").is_err());
+    }
+
+    #[test]
+    fn test_render() {
+        let map = parse(TEST_INPUT).unwrap();
+        let render = map.render(3, 1);
+        assert_eq!(
+            render.to_text(),
+            "..##.........##.........##.......
+#..O#...#..#...#...#..#...#...#..
+.#....X..#..#....#..#..#....#..#.
+..#.#...#O#..#.#...#.#..#.#...#.#
+.#...##..#..X...##..#..#...##..#.
+..#.##.......#.X#.......#.##.....
+.#.#.#....#.#.#.#.O..#.#.#.#....#
+.#........#.#........X.#........#
+#.##...#...#.##...#...#.X#...#...
+#...##....##...##....##...#X....#
+.#..#...#.#.#..#...#.#.#..#...X.#"
+        );
+        let text = map.render(-1, 2).to_text();
+        let lines: Vec<&str> = text.lines().collect();
+        // one tile to the left of the start, plus the tile the path starts on
+        assert_eq!(lines[0].len(), 22);
+        assert_eq!(lines[2].chars().nth(10), Some('O'));
+        assert_eq!(lines[4].chars().nth(9), Some('X'));
+        assert_eq!(lines[10].chars().nth(6), Some('O'));
+        assert_eq!(text.matches(['O', 'X']).count(), 5);
+    }
+
+    #[test]
+    fn test_render_images() {
+        let map = parse("#.\n.#").unwrap();
+        let render = map.render(1, 1);
+        let cells: Vec<&[Cell]> = render.cells.rows().collect();
+        assert_eq!(
+            cells,
+            vec![&[Cell::Tree, Cell::Snow][..], &[Cell::Snow, Cell::Hit][..]]
+        );
+        let ppm = render.to_ppm(2);
+        assert!(ppm.starts_with(b"P6\n4 4\n255\n"));
+        assert_eq!(ppm.len(), "P6\n4 4\n255\n".len() + 4 * 4 * 3);
+        let svg = render.to_svg(10);
+        assert!(svg
+            .starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"20\" height=\"20\">"));
+        assert_eq!(svg.matches("<rect").count(), 4);
+        assert!(svg.contains(
+            "<rect x=\"10\" y=\"10\" width=\"10\" height=\"10\" fill=\"rgb(220,20,60)\"/>"
+        ));
     }
 }
@@ -1,163 +1,626 @@
+use crate::error::{records, ConfigError, ParseError, ParseErrorKind};
+use regex::Regex;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+/// One passport record; every field is optional as records are often incomplete. Values are
+/// kept as written, so a malformed year is a validation failure rather than a parse error.
 #[derive(Debug)]
-struct Passport {
-    birth_year: Option<usize>,
-    issue_year: Option<usize>,
-    exp_year: Option<usize>,
-    height: Option<String>,
-    hair_color: Option<String>,
-    eye_color: Option<String>,
-    pid: Option<String>,
-    cid: Option<usize>,
-}
-
-impl From<&str> for Passport {
-    fn from(s: &str) -> Self {
-        let (
-            mut birth_year,
-            mut issue_year,
-            mut exp_year,
-            mut height,
-            mut hair_color,
-            mut eye_color,
-            mut pid,
-            mut cid,
-        ) = (
-            Option::None,
-            Option::None,
-            Option::None,
-            Option::None,
-            Option::None,
-            Option::None,
-            Option::None,
-            Option::None,
-        );
-        s.split(" ").filter(|i| !i.is_empty()).for_each(|i| {
-            let name_var: Vec<&str> = i.split(":").collect();
-            match name_var[0] {
-                "byr" => birth_year = Option::Some(name_var[1].parse().unwrap()),
-                "iyr" => issue_year = Option::Some(name_var[1].parse().unwrap()),
-                "eyr" => exp_year = Option::Some(name_var[1].parse().unwrap()),
-                "hgt" => height = Option::Some(String::from(name_var[1])),
-                "hcl" => hair_color = Option::Some(String::from(name_var[1])),
-                "ecl" => eye_color = Option::Some(String::from(name_var[1])),
-                "pid" => pid = Option::Some(String::from(name_var[1])),
-                "cid" => cid = Option::Some(name_var[1].parse().unwrap()),
-                _ => {}
+pub struct Passport {
+    /// `byr`
+    pub birth_year: Option<String>,
+    /// `iyr`
+    pub issue_year: Option<String>,
+    /// `eyr`
+    pub exp_year: Option<String>,
+    /// `hgt`, including its `cm` or `in` unit
+    pub height: Option<String>,
+    /// `hcl`
+    pub hair_color: Option<String>,
+    /// `ecl`
+    pub eye_color: Option<String>,
+    /// `pid`
+    pub pid: Option<String>,
+    /// `cid`
+    pub cid: Option<String>,
+    /// Any other fields, by name.
+    pub other: BTreeMap<String, String>,
+    /// The line the record starts on, 1-based.
+    pub line: usize,
+    /// Fields that were repeated or that the puzzle does not define.
+    pub warnings: Vec<Warning>,
+}
+
+/// Something odd about a record that does not stop it being read.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum WarningKind {
+    /// The field appeared earlier in the record; the first value is kept.
+    Duplicate,
+    /// The puzzle does not define the field; it is kept in [`Passport::other`].
+    Unknown,
+}
+
+/// A field worth a second look, located by line and column (both 1-based).
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Warning {
+    pub line: usize,
+    pub column: usize,
+    pub field: String,
+    pub kind: WarningKind,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {} column {}: ", self.line, self.column)?;
+        match self.kind {
+            WarningKind::Duplicate => {
+                write!(f, "duplicate field `{}`, keeping the first", self.field)
+            }
+            WarningKind::Unknown => write!(f, "unknown field `{}`", self.field),
+        }
+    }
+}
+
+impl FromStr for Passport {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut passport = Passport {
+            birth_year: Option::None,
+            issue_year: Option::None,
+            exp_year: Option::None,
+            height: Option::None,
+            hair_color: Option::None,
+            eye_color: Option::None,
+            pid: Option::None,
+            cid: Option::None,
+            other: BTreeMap::new(),
+            line: 1,
+            warnings: vec![],
+        };
+        for field in s.split_whitespace() {
+            let mut name_var = field.splitn(2, ':');
+            let name = name_var.next().unwrap_or_default();
+            let value = name_var.next().ok_or_else(|| {
+                ParseError::located(4, s, field, ParseErrorKind::Missing("field value"))
+            })?;
+            let warn = |kind| {
+                let at = ParseError::located(4, s, field, ParseErrorKind::Invalid("field"));
+                Warning {
+                    line: at.line,
+                    column: at.column,
+                    field: String::from(name),
+                    kind,
+                }
+            };
+            if passport.value(name).is_some() {
+                passport.warnings.push(warn(WarningKind::Duplicate));
+                continue;
+            }
+            let value = String::from(value);
+            match name {
+                "byr" => passport.birth_year = Option::Some(value),
+                "iyr" => passport.issue_year = Option::Some(value),
+                "eyr" => passport.exp_year = Option::Some(value),
+                "hgt" => passport.height = Option::Some(value),
+                "hcl" => passport.hair_color = Option::Some(value),
+                "ecl" => passport.eye_color = Option::Some(value),
+                "pid" => passport.pid = Option::Some(value),
+                "cid" => passport.cid = Option::Some(value),
+                _ => {
+                    passport.warnings.push(warn(WarningKind::Unknown));
+                    passport.other.insert(String::from(name), value);
+                }
             }
-        });
-        Passport {
-            birth_year,
-            issue_year,
-            exp_year,
-            height,
-            hair_color,
-            eye_color,
-            pid,
-            cid,
         }
+        Ok(passport)
     }
 }
 
 impl Passport {
+    /// The value of the field `key` as written in the record, if present.
+    pub fn value(&self, key: &str) -> Option<&str> {
+        match key {
+            "byr" => self.birth_year.as_deref(),
+            "iyr" => self.issue_year.as_deref(),
+            "eyr" => self.exp_year.as_deref(),
+            "hgt" => self.height.as_deref(),
+            "hcl" => self.hair_color.as_deref(),
+            "ecl" => self.eye_color.as_deref(),
+            "pid" => self.pid.as_deref(),
+            "cid" => self.cid.as_deref(),
+            _ => self.other.get(key).map(String::as_str),
+        }
+    }
+
+    /// Whether every field other than `cid` is present.
     pub fn is_valid(&self) -> bool {
-        self.birth_year.is_some()
-            && self.issue_year.is_some()
-            && self.exp_year.is_some()
-            && self.height.is_some()
-            && self.hair_color.is_some()
-            && self.eye_color.is_some()
-            && self.pid.is_some()
+        Schema::puzzle().is_complete(self)
     }
 
+    /// Whether every field other than `cid` is present and holds an acceptable value.
     pub fn is_valid_strict(&self) -> bool {
-        self.valid_birth_year()
-            && self.valid_issue_year()
-            && self.valid_exp_year()
-            && self.valid_hgt()
-            && self.valid_hair()
-            && self.valid_eyes()
-            && self.valid_pid()
+        Schema::puzzle().is_valid(self)
     }
+}
+
+/// The puzzle's passport rules, used by [`Schema::default`].
+pub const DEFAULT_SCHEMA: &str = r##"
+[byr]
+type = "number"
+min = 1920
+max = 2002
+
+[iyr]
+type = "number"
+min = 2010
+max = 2020
+
+[eyr]
+type = "number"
+min = 2020
+max = 2030
+
+[hgt.units]
+cm = { min = 150, max = 193 }
+in = { min = 59, max = 76 }
+
+[hcl]
+regex = "^#[0-9a-f]{6}$"
+
+[ecl]
+one_of = ["amb", "blu", "brn", "gry", "grn", "hzl", "oth"]
+
+[pid]
+regex = "^[0-9]{9}$"
+
+[cid]
+required = false
+"##;
 
-    fn valid_birth_year(&self) -> bool {
-        (1920..=2002).contains(&self.birth_year.unwrap_or_default())
+/// An inclusive range of numbers; either end may be left open.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct Bounds {
+    pub min: Option<u64>,
+    pub max: Option<u64>,
+}
+
+impl Bounds {
+    pub fn contains(&self, n: u64) -> bool {
+        self.check(n).is_none()
     }
 
-    fn valid_issue_year(&self) -> bool {
-        (2010..=2020).contains(&self.issue_year.unwrap_or_default())
+    /// Which end of the range `n` falls outside, if either.
+    fn check(&self, n: u64) -> Option<Failure> {
+        match (self.min, self.max) {
+            (Some(min), _) if n < min => Some(Failure::BelowMinimum(min)),
+            (_, Some(max)) if n > max => Some(Failure::AboveMaximum(max)),
+            _ => None,
+        }
     }
+}
+
+/// What a field's value is read as.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum FieldType {
+    #[default]
+    Text,
+    /// A whole number, checked against the field's bounds.
+    Number,
+}
+
+/// The rules for one passport field. A value must pass every check that is set.
+#[derive(Debug, Clone)]
+pub struct FieldRule {
+    pub required: bool,
+    pub kind: FieldType,
+    /// The allowed numbers, for [`FieldType::Number`] fields.
+    pub bounds: Bounds,
+    /// For measurements, the allowed amount for each unit suffix, e.g. `cm`; a value must end
+    /// in one of the units.
+    pub units: BTreeMap<String, Bounds>,
+    /// The only values allowed, if set.
+    pub one_of: Option<Vec<String>>,
+    pub regex: Option<Regex>,
+}
 
-    fn valid_exp_year(&self) -> bool {
-        (2020..=2030).contains(&self.exp_year.unwrap_or_default())
+impl FieldRule {
+    /// Whether `value` passes every check.
+    pub fn accepts(&self, value: &str) -> bool {
+        self.failures(value).is_empty()
     }
 
-    fn valid_hgt(&self) -> bool {
-        if let Some(height) = self.height.as_ref() {
-            let range = match &height[height.len() - 2..] {
-                "in" => (59..=76),
-                "cm" => (150..=193),
-                _ => return false,
-            };
-            range.contains(&height[0..height.len() - 2].parse::<usize>().unwrap_or(0))
-        } else {
-            false
+    /// Every check `value` fails.
+    pub fn failures(&self, value: &str) -> Vec<Failure> {
+        let number = |n: &str, bounds: &Bounds| match n.parse() {
+            Ok(n) => bounds.check(n),
+            Err(_) => Some(Failure::NotANumber),
+        };
+        let mut failures = vec![];
+        if self.kind == FieldType::Number {
+            failures.extend(number(value, &self.bounds));
+        }
+        if !self.units.is_empty() {
+            // the longest suffix wins, so `mm` is not read as `m`
+            let amount = self
+                .units
+                .iter()
+                .filter_map(|(unit, bounds)| {
+                    Some((unit, value.strip_suffix(unit.as_str())?, bounds))
+                })
+                .max_by_key(|(unit, _, _)| unit.len());
+            match amount {
+                Some((_, n, bounds)) => failures.extend(number(n, bounds)),
+                None => failures.push(Failure::UnknownUnit),
+            }
+        }
+        if let Some(values) = &self.one_of {
+            if !values.iter().any(|v| v == value) {
+                failures.push(Failure::NotAllowed);
+            }
+        }
+        if let Some(re) = &self.regex {
+            if !re.is_match(value) {
+                failures.push(Failure::NoMatch(re.to_string()));
+            }
+        }
+        failures
+    }
+}
+
+/// Why a passport field was rejected.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Failure {
+    Missing,
+    NotANumber,
+    BelowMinimum(u64),
+    AboveMaximum(u64),
+    /// The value does not end in any of the field's units.
+    UnknownUnit,
+    /// The value is not one of the field's `one_of` values.
+    NotAllowed,
+    /// The value does not match the field's regex, which is included.
+    NoMatch(String),
+}
+
+impl Failure {
+    /// A short name for the kind of failure, without its details.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Failure::Missing => "missing",
+            Failure::NotANumber => "not a number",
+            Failure::BelowMinimum(_) => "below minimum",
+            Failure::AboveMaximum(_) => "above maximum",
+            Failure::UnknownUnit => "unknown unit",
+            Failure::NotAllowed => "not allowed",
+            Failure::NoMatch(_) => "no match",
         }
     }
+}
+
+/// One way a passport breaks its schema.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Violation {
+    pub field: String,
+    /// The rejected value, or `None` when the field is missing.
+    pub value: Option<String>,
+    pub failure: Failure,
+}
+
+impl Violation {
+    /// The field and kind of failure, e.g. `byr above maximum`, for grouping violations.
+    pub fn rule(&self) -> String {
+        format!("{} {}", self.field, self.failure.name())
+    }
+}
 
-    fn valid_hair(&self) -> bool {
-        Passport::valid_str(self.hair_color.as_ref(), r"^#[0-9a-f]{6}$")
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = self.value.as_deref().unwrap_or_default();
+        match &self.failure {
+            Failure::Missing => write!(f, "`{}` is missing", self.field),
+            Failure::NotANumber => write!(f, "`{}` `{}` is not a number", self.field, value),
+            Failure::BelowMinimum(min) => {
+                write!(f, "`{}` `{}` is below {}", self.field, value, min)
+            }
+            Failure::AboveMaximum(max) => {
+                write!(f, "`{}` `{}` is above {}", self.field, value, max)
+            }
+            Failure::UnknownUnit => write!(f, "`{}` `{}` has no known unit", self.field, value),
+            Failure::NotAllowed => write!(f, "`{}` `{}` is not allowed", self.field, value),
+            Failure::NoMatch(re) => {
+                write!(f, "`{}` `{}` does not match `{}`", self.field, value, re)
+            }
+        }
     }
+}
 
-    fn valid_eyes(&self) -> bool {
-        Passport::valid_str(self.eye_color.as_ref(), r"^amb|blu|brn|gry|grn|hzl|oth$")
+/// Every violation of one passport.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Report {
+    /// The line the passport's record starts on, 1-based.
+    pub line: usize,
+    pub violations: Vec<Violation>,
+}
+
+impl Report {
+    pub fn is_valid(&self) -> bool {
+        self.violations.is_empty()
     }
+}
+
+/// Checks every passport against `schema`, listing each violation.
+pub fn validate(passports: &[Passport], schema: &Schema) -> Vec<Report> {
+    passports
+        .iter()
+        .map(|p| Report {
+            line: p.line,
+            violations: schema.violations(p),
+        })
+        .collect()
+}
 
-    fn valid_pid(&self) -> bool {
-        Passport::valid_str(self.pid.as_ref(), r"^[0-9]{9}$")
+/// How many times each rule (see [`Violation::rule`]) was broken across `reports`, most often
+/// broken first.
+pub fn histogram(reports: &[Report]) -> Vec<(String, usize)> {
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    for v in reports.iter().flat_map(|r| &r.violations) {
+        *counts.entry(v.rule()).or_default() += 1;
     }
+    let mut counts: Vec<_> = counts.into_iter().collect();
+    counts.sort_by_key(|&(_, n)| std::cmp::Reverse(n));
+    counts
+}
+
+/// Which fields a passport needs and what values they may hold, keyed by field name.
+///
+/// Schemas are written in TOML or JSON with a table per field:
+///
+/// ```toml
+/// [byr]
+/// type = "number"       # or "text", the default
+/// min = 1920
+/// max = 2002
+///
+/// [hgt.units]
+/// cm = { min = 150, max = 193 }
+/// in = { min = 59, max = 76 }
+///
+/// [ecl]
+/// one_of = ["amb", "blu", "brn"]
+///
+/// [hcl]
+/// regex = "^#[0-9a-f]{6}$"
+///
+/// [cid]
+/// required = false      # fields are required by default
+/// ```
+///
+/// Fields the schema does not mention are ignored. A `regex` matches anywhere in the value unless
+/// anchored, and `^a|b$` only anchors `a` at the start and `b` at the end, so prefer `one_of` for a
+/// fixed list of values.
+#[derive(Debug, Clone)]
+pub struct Schema {
+    pub fields: BTreeMap<String, FieldRule>,
+}
+
+fn invalid(message: String) -> ConfigError {
+    ConfigError::new("passport schema", message)
+}
 
-    fn valid_str(maybe_str: Option<&String>, re: &str) -> bool {
-        if let Some(str) = maybe_str {
-            let re = regex::Regex::new(re).unwrap();
-            let captures = re.captures(str.as_str());
-            captures.is_some()
-        } else {
-            false
+fn bounds_from_value(field: &str, value: &Value) -> Result<Bounds, ConfigError> {
+    let object = value.as_object().ok_or_else(|| {
+        invalid(format!(
+            "expected a table for `{}`, found `{}`",
+            field, value
+        ))
+    })?;
+    let mut bounds = Bounds::default();
+    for (key, v) in object {
+        let n = v
+            .as_u64()
+            .ok_or_else(|| invalid(format!("bad `{}` for `{}`: `{}`", key, field, v)))?;
+        match key.as_str() {
+            "min" => bounds.min = Some(n),
+            "max" => bounds.max = Some(n),
+            _ => return Err(invalid(format!("unknown key `{}` for `{}`", key, field))),
+        }
+    }
+    if let (Some(min), Some(max)) = (bounds.min, bounds.max) {
+        if min > max {
+            return Err(invalid(format!("`{}` has `min` above `max`", field)));
         }
     }
+    Ok(bounds)
 }
 
-#[aoc_generator(day4)]
-fn input_to_vec(input: &str) -> Vec<Passport> {
-    let mut cleaned_str = String::from("");
-    let mut cleaned_input: Vec<Passport> = vec![];
-    input.lines().for_each(|i| {
-        if i.is_empty() && !cleaned_str.is_empty() {
-            cleaned_input.push(Passport::from(cleaned_str.as_str()));
-            cleaned_str = String::from("");
+fn rule_from_value(field: &str, value: &Value) -> Result<FieldRule, ConfigError> {
+    let object = value.as_object().ok_or_else(|| {
+        invalid(format!(
+            "expected a table for `{}`, found `{}`",
+            field, value
+        ))
+    })?;
+    let mut rule = FieldRule {
+        required: true,
+        kind: FieldType::Text,
+        bounds: Bounds::default(),
+        units: BTreeMap::new(),
+        one_of: None,
+        regex: None,
+    };
+    let mut bounds = serde_json::Map::new();
+    let mut kind = None;
+    for (key, v) in object {
+        let bad = || invalid(format!("bad `{}` for `{}`: `{}`", key, field, v));
+        match key.as_str() {
+            "required" => rule.required = v.as_bool().ok_or_else(bad)?,
+            "type" => {
+                kind = match v.as_str() {
+                    Some("text") => Some(FieldType::Text),
+                    Some("number") => Some(FieldType::Number),
+                    _ => return Err(bad()),
+                }
+            }
+            "min" | "max" => {
+                bounds.insert(key.clone(), v.clone());
+            }
+            "units" => {
+                for (unit, b) in v.as_object().ok_or_else(bad)? {
+                    let name = format!("{}.units.{}", field, unit);
+                    rule.units
+                        .insert(unit.clone(), bounds_from_value(&name, b)?);
+                }
+            }
+            "one_of" => {
+                let values = v
+                    .as_array()
+                    .and_then(|a| {
+                        a.iter()
+                            .map(|w| w.as_str().map(String::from))
+                            .collect::<Option<Vec<String>>>()
+                    })
+                    .ok_or_else(bad)?;
+                rule.one_of = Some(values);
+            }
+            "regex" => {
+                let pattern = v.as_str().ok_or_else(bad)?;
+                let regex = Regex::new(pattern)
+                    .map_err(|e| invalid(format!("bad `regex` for `{}`: {}", field, e)))?;
+                rule.regex = Some(regex);
+            }
+            _ => return Err(invalid(format!("unknown key `{}` for `{}`", key, field))),
+        }
+    }
+    rule.bounds = bounds_from_value(field, &Value::Object(bounds.clone()))?;
+    rule.kind = match kind {
+        Some(FieldType::Text) if !bounds.is_empty() => {
+            return Err(invalid(format!(
+                "`{}` has bounds but is not a number",
+                field
+            )))
+        }
+        Some(kind) => kind,
+        None if !bounds.is_empty() => FieldType::Number,
+        None => FieldType::Text,
+    };
+    Ok(rule)
+}
+
+impl Schema {
+    fn from_value(value: &Value) -> Result<Self, ConfigError> {
+        let fields = value
+            .as_object()
+            .ok_or_else(|| invalid(format!("expected a table of fields, found `{}`", value)))?
+            .iter()
+            .map(|(field, rule)| Ok((field.clone(), rule_from_value(field, rule)?)))
+            .collect::<Result<_, ConfigError>>()?;
+        Ok(Schema { fields })
+    }
+
+    /// Reads a schema written in JSON, with an object per field.
+    pub fn from_json(text: &str) -> Result<Self, ConfigError> {
+        let value: Value = serde_json::from_str(text).map_err(|e| invalid(e.to_string()))?;
+        Schema::from_value(&value)
+    }
+
+    /// Reads a schema written in TOML, with a table per field.
+    pub fn from_toml(text: &str) -> Result<Self, ConfigError> {
+        let value: toml::Value = toml::from_str(text).map_err(|e| invalid(e.to_string()))?;
+        Schema::from_value(&serde_json::to_value(value).map_err(|e| invalid(e.to_string()))?)
+    }
+
+    /// Whether `passport` has every required field, whatever their values.
+    pub fn is_complete(&self, passport: &Passport) -> bool {
+        self.fields
+            .iter()
+            .all(|(key, rule)| !rule.required || passport.value(key).is_some())
+    }
+
+    /// Whether `passport` has every required field and every field it has is acceptable.
+    pub fn is_valid(&self, passport: &Passport) -> bool {
+        self.fields
+            .iter()
+            .all(|(key, rule)| match passport.value(key) {
+                Some(value) => rule.accepts(value),
+                None => !rule.required,
+            })
+    }
+
+    /// Every missing required field and every check a field of `passport` fails, in field order.
+    pub fn violations(&self, passport: &Passport) -> Vec<Violation> {
+        let mut violations = vec![];
+        for (key, rule) in &self.fields {
+            let value = passport.value(key);
+            let failures = match value {
+                Some(value) => rule.failures(value),
+                None if rule.required => vec![Failure::Missing],
+                None => vec![],
+            };
+            violations.extend(failures.into_iter().map(|failure| Violation {
+                field: key.clone(),
+                value: value.map(String::from),
+                failure,
+            }));
         }
-        cleaned_str += i;
-        cleaned_str += " ";
-    });
-    if !cleaned_str.is_empty() {
-        cleaned_input.push(Passport::from(cleaned_str.as_str()));
+        violations
+    }
+}
+
+impl Schema {
+    /// The puzzle's rules, from [`DEFAULT_SCHEMA`]. They are read and their regexes compiled
+    /// the first time they are needed, then shared.
+    pub fn puzzle() -> &'static Schema {
+        static PUZZLE: OnceLock<Schema> = OnceLock::new();
+        PUZZLE
+            .get_or_init(|| Schema::from_toml(DEFAULT_SCHEMA).expect("the default schema is valid"))
     }
-    cleaned_input
 }
 
+impl Default for Schema {
+    /// The puzzle's rules, from [`DEFAULT_SCHEMA`].
+    fn default() -> Self {
+        Schema::puzzle().clone()
+    }
+}
+
+/// Parses the batch file into passports; records are separated by blank lines.
+#[aoc_generator(day4)]
+pub fn parse(input: &str) -> Result<Vec<Passport>, ParseError> {
+    records(input)
+        .into_iter()
+        .map(|(line, record)| {
+            let mut passport: Passport = record.parse().map_err(|e: ParseError| e.offset(line))?;
+            passport.line = line + 1;
+            for w in &mut passport.warnings {
+                w.line += line;
+            }
+            Ok(passport)
+        })
+        .collect()
+}
+
+/// How many passports have all the required fields.
 #[aoc(day4, part1)]
-fn valid_count(input: &Vec<Passport>) -> usize {
-    input.iter().filter(|p| p.is_valid()).count()
+pub fn part1(input: &[Passport]) -> usize {
+    let schema = Schema::puzzle();
+    input.iter().filter(|p| schema.is_complete(p)).count()
 }
 
+/// How many passports have all the required fields with valid values.
 #[aoc(day4, part2)]
-fn strict_valid_count(input: &Vec<Passport>) -> usize {
-    input.iter().filter(|p| p.is_valid_strict()).count()
+pub fn part2(input: &[Passport]) -> usize {
+    let schema = Schema::puzzle();
+    input.iter().filter(|p| schema.is_valid(p)).count()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
 
     const TEST_INPUT: &str = "ecl:gry pid:860033327 eyr:2020 hcl:#fffffd
 byr:1937 iyr:2017 cid:147 hgt:183cm
@@ -175,8 +638,8 @@ iyr:2011 ecl:brn hgt:59in";
 
     #[test]
     fn test_valid_count() {
-        let input = input_to_vec(TEST_INPUT);
-        assert_eq!(valid_count(&input), 2);
+        let input = parse(TEST_INPUT).unwrap();
+        assert_eq!(part1(&input), 2);
     }
 
     #[test]
@@ -206,24 +669,65 @@ ecl:brn hgt:182cm pid:021572410 eyr:2020 byr:1992 cid:277
 hgt:59cm ecl:zzz
 eyr:2038 hcl:74454a iyr:2023
 pid:3556412378 byr:2007";
-        let input = input_to_vec(valids);
-        assert_eq!(strict_valid_count(&input), 4);
-        let input = input_to_vec(TEST_INPUT);
-        assert_eq!(strict_valid_count(&input), 2);
-        let input = input_to_vec(invalids);
-        assert_eq!(strict_valid_count(&input), 0);
+        let input = parse(valids).unwrap();
+        assert_eq!(part2(&input), 4);
+        let input = parse(TEST_INPUT).unwrap();
+        assert_eq!(part2(&input), 2);
+        let input = parse(invalids).unwrap();
+        assert_eq!(part2(&input), 0);
+    }
+
+    #[test]
+    fn test_bad_year() {
+        let input = parse("ecl:gry pid:860033327\n\nhcl:#fffffd\nbyr:19x7 iyr:2017").unwrap();
+        assert_eq!(input[1].birth_year.as_deref(), Some("19x7"));
+        let reports = validate(&input, Schema::puzzle());
+        assert_eq!(reports[1].violations[0].rule(), "byr not a number");
+        let e = parse("ecl:gry\n\nhcl:#fffffd\nbyr iyr:2017").unwrap_err();
+        assert_eq!((e.line, e.column, e.text.as_str()), (4, 1, "byr"));
+    }
+
+    #[test]
+    fn test_messy_records() {
+        let input = parse(
+            "\r\necl:gry\tpid:860033327 \r\nbyr:1937\r\n\r\n \t\r\n\n\
+             hcl:#ae17e1 iyr:2013 ecl:brn\r\nzzz:1 iyr:2020 ecl:blu\r\n",
+        )
+        .unwrap();
+        assert_eq!(input.len(), 2);
+        assert_eq!(input[0].line, 2);
+        assert_eq!(input[0].pid.as_deref(), Some("860033327"));
+        assert_eq!(input[0].birth_year.as_deref(), Some("1937"));
+        assert!(input[0].warnings.is_empty());
+        let p = &input[1];
+        assert_eq!(p.line, 7);
+        assert_eq!(p.issue_year.as_deref(), Some("2013"));
+        assert_eq!(p.eye_color.as_deref(), Some("brn"));
+        assert_eq!(p.value("zzz"), Some("1"));
+        let warnings: Vec<String> = p.warnings.iter().map(|w| w.to_string()).collect();
+        assert_eq!(
+            warnings,
+            vec![
+                "line 8 column 1: unknown field `zzz`",
+                "line 8 column 7: duplicate field `iyr`, keeping the first",
+                "line 8 column 16: duplicate field `ecl`, keeping the first",
+            ]
+        );
     }
 
     fn valid_passport() -> Passport {
         Passport {
-            birth_year: Option::Some(2000),
-            issue_year: Option::Some(2010),
-            exp_year: Option::Some(2021),
+            birth_year: Option::Some(String::from("2000")),
+            issue_year: Option::Some(String::from("2010")),
+            exp_year: Option::Some(String::from("2021")),
             height: Option::Some(String::from("155cm")),
             hair_color: Option::Some(String::from("#123abc")),
             eye_color: Option::Some(String::from("brn")),
             pid: Option::Some(String::from("000000001")),
             cid: Option::None,
+            other: BTreeMap::new(),
+            line: 1,
+            warnings: vec![],
         }
     }
 
@@ -244,4 +748,183 @@ pid:3556412378 byr:2007";
             assert!(!p.is_valid_strict());
         }
     }
+
+    #[test]
+    fn test_schema() {
+        let schema = Schema::from_json(
+            r#"{
+                "hgt": {"units": {"cm": {"min": 150, "max": 193}, "in": {"min": 59}}},
+                "ecl": {"one_of": ["amb", "blu"]},
+                "pid": {"type": "number", "max": 999999999},
+                "tsa": {"regex": "^[A-Z]{2}$"},
+                "cid": {"required": false, "type": "number"}
+            }"#,
+        )
+        .unwrap();
+        let passport = |text: &str| text.parse::<Passport>().unwrap();
+        let p = passport("hgt:160cm ecl:blu pid:012345678 tsa:OK byr:1800");
+        assert!(schema.is_valid(&p));
+        assert!(!Schema::default().is_complete(&p));
+        assert!(schema.is_valid(&passport("hgt:99in ecl:amb pid:1 tsa:OK cid:7")));
+        assert!(!schema.is_complete(&passport("hgt:160cm ecl:blu pid:012345678")));
+        for bad in &[
+            "hgt:160 ecl:blu pid:1 tsa:OK",
+            "hgt:58in ecl:blu pid:1 tsa:OK",
+            "hgt:160cm ecl:blue pid:1 tsa:OK",
+            "hgt:160cm ecl:blu pid:1234567890 tsa:OK",
+            "hgt:160cm ecl:blu pid:1 tsa:ok",
+        ] {
+            let p = passport(bad);
+            assert!(schema.is_complete(&p), "{}", bad);
+            assert!(!schema.is_valid(&p), "{}", bad);
+        }
+
+        let toml =
+            Schema::from_toml("[byr]\nmin = 1920\nmax = 2002\n\n[cid]\nrequired = false").unwrap();
+        assert_eq!(toml.fields["byr"].kind, FieldType::Number);
+        assert!(toml.is_valid(&passport("byr:2002")));
+        assert!(!toml.is_valid(&passport("byr:2003")));
+    }
+
+    #[test]
+    fn test_invalid_schema() {
+        for json in &[
+            r#"{"byr": {"min": "1920"}}"#,
+            r#"{"byr": {"min": 2000, "max": 1920}}"#,
+            r#"{"byr": {"type": "text", "min": 1920}}"#,
+            r#"{"byr": {"type": "date"}}"#,
+            r#"{"byr": {"range": [1920, 2002]}}"#,
+            r#"{"hgt": {"units": {"cm": 150}}}"#,
+            r##"{"hcl": {"regex": "#[0-9"}}"##,
+            r#"{"ecl": {"one_of": "amb"}}"#,
+            r#"{"cid": {"required": "no"}}"#,
+            r#"["byr"]"#,
+        ] {
+            assert!(Schema::from_json(json).is_err(), "{}", json);
+        }
+        let e = Schema::from_toml("[byr]\nminimum = 1").unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "invalid passport schema: unknown key `minimum` for `byr`"
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let input = parse(
+            "eyr:1972 cid:100
+hcl:#18171d ecl:amb hgt:170 pid:186cm iyr:2018 byr:1926
+
+iyr:2019
+hcl:#602927 eyr:1967 hgt:170cm
+ecl:grn pid:012533040 byr:1946
+
+hcl:dab227 iyr:2012
+ecl:brn hgt:182cm pid:021572410 eyr:2020 byr:1992 cid:277
+
+byr:1937
+
+pid:087499704 hgt:74in ecl:grn iyr:2012 eyr:2030 byr:1980 hcl:#623a2f",
+        )
+        .unwrap();
+        let reports = validate(&input, &Schema::default());
+        let lines: Vec<usize> = reports.iter().map(|r| r.line).collect();
+        assert_eq!(lines, vec![1, 4, 8, 11, 13]);
+        let messages: Vec<String> = reports[0]
+            .violations
+            .iter()
+            .map(|v| v.to_string())
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                "`eyr` `1972` is below 2020",
+                "`hgt` `170` has no known unit",
+                "`pid` `186cm` does not match `^[0-9]{9}$`",
+            ]
+        );
+        assert_eq!(
+            reports[1].violations,
+            vec![Violation {
+                field: String::from("eyr"),
+                value: Some(String::from("1967")),
+                failure: Failure::BelowMinimum(2020),
+            }]
+        );
+        assert_eq!(reports[2].violations[0].rule(), "hcl no match");
+        assert_eq!(reports[3].violations.len(), 6);
+        assert!(reports[3]
+            .violations
+            .iter()
+            .all(|v| v.failure == Failure::Missing && v.value.is_none()));
+        assert!(reports[4].is_valid());
+
+        let counts = histogram(&reports);
+        assert_eq!(counts[0], (String::from("eyr below minimum"), 2));
+        assert_eq!(counts[1], (String::from("ecl missing"), 1));
+        assert_eq!(counts.len(), 10);
+    }
+
+    #[test]
+    fn test_eye_colour_anchoring() {
+        let ecl = &Schema::puzzle().fields["ecl"];
+        for colour in &["amb", "blu", "brn", "gry", "grn", "hzl", "oth"] {
+            assert!(ecl.accepts(colour), "{}", colour);
+        }
+        for colour in &["bluish", "ambe", "xoth", "grnhzl", "", "BLU", " blu"] {
+            assert!(!ecl.accepts(colour), "{}", colour);
+        }
+    }
+
+    const COLOURS: [&str; 7] = ["amb", "blu", "brn", "gry", "grn", "hzl", "oth"];
+
+    proptest! {
+        #[test]
+        fn prop_only_exact_eye_colours(colour in "[a-z# ]{0,8}") {
+            let ecl = &Schema::puzzle().fields["ecl"];
+            prop_assert_eq!(ecl.accepts(&colour), COLOURS.contains(&colour.as_str()));
+        }
+
+        #[test]
+        fn prop_no_eye_colour_with_extra_text(
+            i in 0..COLOURS.len(),
+            before in "[a-z ]{0,3}",
+            after in "[a-z ]{0,3}",
+        ) {
+            let colour = format!("{}{}{}", before, COLOURS[i], after);
+            let expected = before.is_empty() && after.is_empty();
+            prop_assert_eq!(Schema::puzzle().fields["ecl"].accepts(&colour), expected);
+        }
+
+        #[test]
+        fn prop_hair_colour(colour in "#?[0-9a-fA-Z]{5,7}") {
+            let expected = colour.len() == 7
+                && colour.starts_with('#')
+                && colour[1..].chars().all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c));
+            prop_assert_eq!(Schema::puzzle().fields["hcl"].accepts(&colour), expected);
+        }
+
+        #[test]
+        fn prop_passport_id(pid in "[0-9a]{7,11}") {
+            let expected = pid.len() == 9 && pid.chars().all(|c| c.is_ascii_digit());
+            prop_assert_eq!(Schema::puzzle().fields["pid"].accepts(&pid), expected);
+        }
+
+        #[test]
+        fn prop_birth_year(year in 0u64..3000) {
+            let expected = (1920..=2002).contains(&year);
+            prop_assert_eq!(Schema::puzzle().fields["byr"].accepts(&year.to_string()), expected);
+        }
+
+        #[test]
+        fn prop_height(amount in 0u64..300, unit in "(cm|in|mm|)") {
+            let expected = match unit.as_str() {
+                "cm" => (150..=193).contains(&amount),
+                "in" => (59..=76).contains(&amount),
+                _ => false,
+            };
+            let height = format!("{}{}", amount, unit);
+            prop_assert_eq!(Schema::puzzle().fields["hgt"].accepts(&height), expected);
+        }
+    }
 }
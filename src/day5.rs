@@ -1,47 +1,303 @@
-fn is_lower(input: &str) -> bool {
-    &input[0..1] == "F" || &input[0..1] == "L"
-}
-
-fn calculate_pos(input: &str, lower: usize, upper: usize) -> usize {
-    if input.len() == 1 {
-        if is_lower(input) {
-            lower
-        } else {
-            upper
-        }
-    } else {
-        let (lower, upper) = if is_lower(input) {
-            (lower, (upper + lower) / 2)
-        } else {
-            ((upper + lower) / 2 + 1, upper)
+use crate::error::{missing, parse_lines, ParseError, ParseErrorKind};
+use crate::grid::Grid;
+use std::fmt;
+use std::str::FromStr;
+
+/// How many letters of a boarding pass pick the row and how many pick the column, i.e. the
+/// aircraft has `2^row_bits` rows of `2^column_bits` seats.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Layout {
+    row_bits: u32,
+    column_bits: u32,
+}
+
+impl Layout {
+    /// The puzzle's aircraft: 128 rows of 8 seats.
+    pub const PUZZLE: Layout = Layout {
+        row_bits: 7,
+        column_bits: 3,
+    };
+
+    /// A layout with the given number of row and column letters, or `None` if seat ids would
+    /// not fit in a `usize`.
+    pub fn new(row_bits: u32, column_bits: u32) -> Option<Self> {
+        if row_bits.checked_add(column_bits)? >= usize::BITS {
+            return None;
+        }
+        Some(Layout {
+            row_bits,
+            column_bits,
+        })
+    }
+
+    pub fn row_bits(&self) -> u32 {
+        self.row_bits
+    }
+
+    pub fn column_bits(&self) -> u32 {
+        self.column_bits
+    }
+
+    pub fn rows(&self) -> usize {
+        1 << self.row_bits
+    }
+
+    pub fn columns(&self) -> usize {
+        1 << self.column_bits
+    }
+
+    /// How many seats there are, which is one more than the highest seat id.
+    pub fn seats(&self) -> usize {
+        self.rows() * self.columns()
+    }
+
+    fn letters(&self) -> usize {
+        (self.row_bits + self.column_bits) as usize
+    }
+
+    /// Decodes a boarding pass written for this layout: `F` (front) or `B` (back) for each row
+    /// letter, then `L` (left) or `R` (right) for each column letter.
+    pub fn decode(&self, s: &str) -> Result<BoardingPass, ParseError> {
+        let mut id = 0;
+        for (i, c) in s.char_indices() {
+            let bit = match (i < self.row_bits as usize, c) {
+                _ if i >= self.letters() => None,
+                (true, 'F') | (false, 'L') => Some(0),
+                (true, 'B') | (false, 'R') => Some(1),
+                _ => None,
+            };
+            match bit {
+                Some(bit) => id = id << 1 | bit,
+                None => {
+                    let part = &s[i..i + c.len_utf8()];
+                    return Err(ParseError::located(
+                        5,
+                        s,
+                        part,
+                        ParseErrorKind::UnexpectedToken,
+                    ));
+                }
+            }
+        }
+        if s.len() < self.letters() {
+            return Err(missing(5, s, "seat letters"));
+        }
+        Ok(BoardingPass { layout: *self, id })
+    }
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Layout::PUZZLE
+    }
+}
+
+/// A seat on an aircraft, written as a boarding pass like `FBFBBFFRLR`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct BoardingPass {
+    pub layout: Layout,
+    id: usize,
+}
+
+impl BoardingPass {
+    /// The pass for seat `id` (`row * columns + column`), or `None` if `layout` has no such seat.
+    pub fn from_id(layout: Layout, id: usize) -> Option<Self> {
+        if id >= layout.seats() {
+            return None;
+        }
+        Some(BoardingPass { layout, id })
+    }
+
+    /// The pass for a row and column, both counted from 0, or `None` if `layout` has no such seat.
+    pub fn from_seat(layout: Layout, row: usize, column: usize) -> Option<Self> {
+        if row >= layout.rows() || column >= layout.columns() {
+            return None;
+        }
+        BoardingPass::from_id(layout, row << layout.column_bits | column)
+    }
+
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    /// The row, counted from 0 at the front.
+    pub fn row(&self) -> usize {
+        self.id >> self.layout.column_bits
+    }
+
+    /// The column, counted from 0 at the left.
+    pub fn column(&self) -> usize {
+        self.id & (self.layout.columns() - 1)
+    }
+}
+
+impl FromStr for BoardingPass {
+    type Err = ParseError;
+
+    /// Decodes a pass for the puzzle's layout.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Layout::PUZZLE.decode(s)
+    }
+}
+
+impl fmt::Display for BoardingPass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (row_bits, column_bits) = (self.layout.row_bits, self.layout.column_bits);
+        for bit in (0..row_bits).rev() {
+            let back = self.row() >> bit & 1 == 1;
+            write!(f, "{}", if back { 'B' } else { 'F' })?;
+        }
+        for bit in (0..column_bits).rev() {
+            let right = self.column() >> bit & 1 == 1;
+            write!(f, "{}", if right { 'R' } else { 'L' })?;
+        }
+        Ok(())
+    }
+}
+
+/// Every seat of an aircraft with how many boarding passes were issued for it.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SeatMap {
+    pub layout: Layout,
+    /// The number of passes for each seat, indexed by `(column, row)`.
+    pub passes: Grid<usize>,
+}
+
+/// The empty seats of a [`SeatMap`], split by where they are.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Vacancies {
+    /// Empty seats before the first occupied one: missing rows at the front of the aircraft and
+    /// the start of the first row in use.
+    pub front: Vec<BoardingPass>,
+    /// Empty seats between the first and last occupied ones.
+    pub gaps: Vec<BoardingPass>,
+    /// Empty seats after the last occupied one.
+    pub back: Vec<BoardingPass>,
+}
+
+impl SeatMap {
+    /// The most seats a map will hold.
+    pub const MAX_SEATS: usize = 1 << 24;
+
+    /// Marks the seat of every pass on an empty `layout`, or `None` if the layout has more than
+    /// [`SeatMap::MAX_SEATS`] seats.
+    ///
+    /// Panics if a pass was decoded for a different layout.
+    pub fn new(layout: Layout, passes: &[BoardingPass]) -> Option<Self> {
+        if layout.seats() > SeatMap::MAX_SEATS {
+            return None;
+        }
+        let mut map = Grid::new(layout.columns(), layout.rows(), 0);
+        for pass in passes {
+            assert_eq!(
+                pass.layout, layout,
+                "boarding pass {} is for another layout",
+                pass
+            );
+            map[(pass.column(), pass.row())] += 1;
+        }
+        Some(SeatMap {
+            layout,
+            passes: map,
+        })
+    }
+
+    fn seats(&self) -> impl Iterator<Item = (BoardingPass, usize)> + '_ {
+        self.passes.iter().map(move |((column, row), &n)| {
+            let pass = BoardingPass::from_seat(self.layout, row, column).unwrap();
+            (pass, n)
+        })
+    }
+
+    pub fn is_occupied(&self, row: usize, column: usize) -> bool {
+        self.passes[(column, row)] > 0
+    }
+
+    /// Every empty seat, in seat id order.
+    pub fn vacancies(&self) -> Vacancies {
+        let occupied: Vec<usize> = self
+            .seats()
+            .filter(|&(_, n)| n > 0)
+            .map(|(p, _)| p.id())
+            .collect();
+        let (first, last) = match (occupied.first(), occupied.last()) {
+            (Some(&first), Some(&last)) => (first, last),
+            _ => {
+                return Vacancies {
+                    front: self.seats().map(|(p, _)| p).collect(),
+                    ..Vacancies::default()
+                }
+            }
         };
-        calculate_pos(&input[1..], lower, upper)
+        let mut vacancies = Vacancies::default();
+        for (pass, _) in self.seats().filter(|&(_, n)| n == 0) {
+            if pass.id() < first {
+                vacancies.front.push(pass);
+            } else if pass.id() > last {
+                vacancies.back.push(pass);
+            } else {
+                vacancies.gaps.push(pass);
+            }
+        }
+        vacancies
     }
+
+    /// Every seat with more than one pass, and how many it has.
+    pub fn duplicates(&self) -> Vec<(BoardingPass, usize)> {
+        self.seats().filter(|&(_, n)| n > 1).collect()
+    }
+
+    /// Draws the aircraft a row per line, front first, each line starting with the row number:
+    /// `.` for an empty seat, `#` for one with a pass, and the number of passes (`+` past 9) for a
+    /// seat with several.
+    pub fn to_text(&self) -> String {
+        let width = (self.layout.rows() - 1).to_string().len();
+        self.passes
+            .rows()
+            .enumerate()
+            .map(|(row, seats)| {
+                let seats: String = seats
+                    .iter()
+                    .map(|&n| match n {
+                        0 => '.',
+                        1 => '#',
+                        2..=9 => std::char::from_digit(n as u32, 10).unwrap(),
+                        _ => '+',
+                    })
+                    .collect();
+                format!("{:>width$} {}", row, seats, width = width)
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// The seat id (`row * 8 + column`) of a ten letter boarding pass such as `FBFBBFFRLR`.
+pub fn calc_boarding_pass(input: &str) -> Result<usize, ParseError> {
+    input.parse::<BoardingPass>().map(|p| p.id())
 }
 
-fn calc_boarding_pass(input: &str) -> usize {
-    calculate_pos(&input[0..7], 0, 127) * 8 + calculate_pos(&input[input.len() - 3..], 0, 7)
+/// Decodes each boarding pass.
+#[aoc_generator(day5)]
+pub fn parse(input: &str) -> Result<Vec<BoardingPass>, ParseError> {
+    parse_lines(input, |l| l.trim_end().parse())
 }
 
+/// The highest seat id on any boarding pass.
 #[aoc(day5, part1)]
-fn max_boarding_pass(input: &str) -> usize {
-    input
-        .lines()
-        .map(|s| calc_boarding_pass(s))
-        .max()
-        .unwrap_or(0)
+pub fn part1(input: &[BoardingPass]) -> usize {
+    input.iter().map(BoardingPass::id).max().unwrap_or(0)
 }
 
+/// The first empty seat id between the lowest and highest, or 0 if there is no gap.
 #[aoc(day5, part2)]
-fn boarding_passes(input: &str) -> usize {
-    let mut v: Vec<usize> = input.lines().map(|s| calc_boarding_pass(s)).collect();
-    v.sort();
-    for (l, r) in v.iter().zip(v[0]..v[v.len() - 1]) {
-        if *l != r {
-            return r;
-        }
-    }
-    0
+pub fn part2(input: &[BoardingPass]) -> usize {
+    SeatMap::new(Layout::PUZZLE, input)
+        .expect("the puzzle's aircraft fits on a seat map")
+        .vacancies()
+        .gaps
+        .first()
+        .map_or(0, BoardingPass::id)
 }
 
 #[cfg(test)]
@@ -50,8 +306,99 @@ mod tests {
 
     #[test]
     fn test_boarding_pass() {
-        assert_eq!(calc_boarding_pass("FBFBBFFRLR"), 357);
-        assert_eq!(calc_boarding_pass("FFFBBBFRRR"), 119);
-        assert_eq!(calc_boarding_pass("BBFFBBFRLL"), 820);
+        assert_eq!(calc_boarding_pass("FBFBBFFRLR"), Ok(357));
+        assert_eq!(calc_boarding_pass("FFFBBBFRRR"), Ok(119));
+        assert_eq!(calc_boarding_pass("BBFFBBFRLL"), Ok(820));
+    }
+
+    #[test]
+    fn test_bad_boarding_pass() {
+        let e = parse("FBFBBFFRLR\nFBFBBFFRL").unwrap_err();
+        assert_eq!((e.line, e.column), (2, 10));
+        let e = parse("FBFBBFLRLR").unwrap_err();
+        assert_eq!((e.column, e.text.as_str()), (7, "L"));
+        let e = parse("FBFBBFFRLRR").unwrap_err();
+        assert_eq!((e.column, e.text.as_str()), (11, "R"));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let pass: BoardingPass = "FBFBBFFRLR".parse().unwrap();
+        assert_eq!((pass.row(), pass.column(), pass.id()), (44, 5, 357));
+        assert_eq!(pass.to_string(), "FBFBBFFRLR");
+        for id in 0..Layout::PUZZLE.seats() {
+            let pass = BoardingPass::from_id(Layout::PUZZLE, id).unwrap();
+            assert_eq!(pass.to_string().parse(), Ok(pass));
+        }
+        assert_eq!(BoardingPass::from_id(Layout::PUZZLE, 1024), None);
+        assert_eq!(
+            BoardingPass::from_seat(Layout::PUZZLE, 44, 5).map(|p| p.id()),
+            Some(357)
+        );
+        assert_eq!(BoardingPass::from_seat(Layout::PUZZLE, 44, 8), None);
+    }
+
+    #[test]
+    fn test_layout() {
+        let layout = Layout::new(4, 2).unwrap();
+        assert_eq!(
+            (layout.rows(), layout.columns(), layout.seats()),
+            (16, 4, 64)
+        );
+        let pass = layout.decode("BFFBRL").unwrap();
+        assert_eq!((pass.row(), pass.column(), pass.id()), (9, 2, 38));
+        assert_eq!(pass.to_string(), "BFFBRL");
+        assert!(layout.decode("FBFBBFFRLR").is_err());
+        assert_eq!(layout.decode("BFFR").unwrap_err().column, 4);
+        assert!(Layout::new(60, 4).is_none());
+        assert_eq!(Layout::new(0, 1).unwrap().decode("R").unwrap().id(), 1);
+    }
+
+    #[test]
+    fn test_seat_map() {
+        let layout = Layout::new(3, 2).unwrap();
+        let passes: Vec<BoardingPass> = [
+            "FFBLR", "FFBRL", "FFBRR", "FBFLL", "FBFRL", "FBFRL", "FBBLL",
+        ]
+        .iter()
+        .map(|p| layout.decode(p).unwrap())
+        .collect();
+        let map = SeatMap::new(layout, &passes).unwrap();
+        assert!(map.is_occupied(1, 1));
+        assert!(!map.is_occupied(1, 0));
+        assert_eq!(
+            map.to_text(),
+            "0 ....
+1 .###
+2 #.2.
+3 #...
+4 ....
+5 ....
+6 ....
+7 ...."
+        );
+        let ids = |seats: &[BoardingPass]| seats.iter().map(|p| p.id()).collect::<Vec<_>>();
+        let vacancies = map.vacancies();
+        assert_eq!(ids(&vacancies.front), vec![0, 1, 2, 3, 4]);
+        assert_eq!(ids(&vacancies.gaps), vec![9, 11]);
+        assert_eq!(ids(&vacancies.back), (13..32).collect::<Vec<_>>());
+        assert_eq!(map.duplicates(), vec![(passes[4], 2)]);
+        assert_eq!(map.duplicates()[0].0.to_string(), "FBFRL");
+
+        let empty = SeatMap::new(layout, &[]).unwrap().vacancies();
+        assert_eq!((empty.front.len(), empty.gaps.len()), (32, 0));
+        assert!(SeatMap::new(Layout::new(30, 30).unwrap(), &[]).is_none());
+        assert!(SeatMap::new(Layout::new(12, 12).unwrap(), &[]).is_some());
+    }
+
+    #[test]
+    fn test_find_seat() {
+        let passes: Vec<BoardingPass> = (100..200)
+            .filter(|&id| id != 150)
+            .map(|id| BoardingPass::from_id(Layout::PUZZLE, id).unwrap())
+            .collect();
+        assert_eq!(part1(&passes), 199);
+        assert_eq!(part2(&passes), 150);
+        assert_eq!(part2(&passes[..10]), 0);
     }
 }
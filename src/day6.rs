@@ -1,55 +1,366 @@
-use std::collections::HashSet;
+use crate::error::{records, ParseError, ParseErrorKind};
+use serde_json::{json, Value};
+use std::fmt;
+use std::ops::{BitAnd, BitOr};
 
-#[aoc_generator(day6)]
-fn to_vec(input: &str) -> Vec<Vec<Vec<char>>> {
-    input
-        .split("\n\n")
-        .map(|i| {
-            i.lines()
-                .map(|s| s.chars().collect::<Vec<char>>())
-                .collect()
+/// The questions a customs form can ask, each answered by writing one character. An alphabet
+/// has at most 64 questions so that answers fit in an [`AnswerSet`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Alphabet {
+    symbols: Vec<char>,
+    /// The question for each ASCII character, or `u8::MAX` for none.
+    ascii: [u8; 128],
+}
+
+impl Alphabet {
+    /// An alphabet asking one question per character of `symbols`, in order, or `None` if a
+    /// character repeats or there are more than 64.
+    pub fn new(symbols: &str) -> Option<Self> {
+        let symbols: Vec<char> = symbols.chars().collect();
+        if symbols.len() > 64 {
+            return None;
+        }
+        let mut ascii = [u8::MAX; 128];
+        for (i, &c) in symbols.iter().enumerate() {
+            if symbols[..i].contains(&c) {
+                return None;
+            }
+            if c.is_ascii() {
+                ascii[c as usize] = i as u8;
+            }
+        }
+        Some(Alphabet { symbols, ascii })
+    }
+
+    /// The puzzle's 26 questions, `a` to `z`.
+    pub fn lowercase() -> Self {
+        Alphabet::new("abcdefghijklmnopqrstuvwxyz").unwrap()
+    }
+
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    /// The question answered by `c`, if it is in the alphabet.
+    pub fn index(&self, c: char) -> Option<usize> {
+        if c.is_ascii() {
+            Some(self.ascii[c as usize])
+                .filter(|&i| i != u8::MAX)
+                .map(usize::from)
+        } else {
+            self.symbols.iter().position(|&s| s == c)
+        }
+    }
+
+    /// The character for question `i`.
+    pub fn symbol(&self, i: usize) -> char {
+        self.symbols[i]
+    }
+
+    /// Every question.
+    pub fn all(&self) -> AnswerSet {
+        AnswerSet::first(self.len())
+    }
+
+    /// The questions answered on one person's line of a form.
+    pub fn answers(&self, line: &str) -> Option<AnswerSet> {
+        line.chars()
+            .try_fold(AnswerSet::EMPTY, |set, c| Some(set.with(self.index(c)?)))
+    }
+
+    /// Writes the questions in `set` as their characters, in alphabet order.
+    pub fn format(&self, set: AnswerSet) -> String {
+        set.iter().map(|i| self.symbol(i)).collect()
+    }
+}
+
+impl Default for Alphabet {
+    fn default() -> Self {
+        Alphabet::lowercase()
+    }
+}
+
+/// A set of questions, bit `i` set when question `i` of the alphabet was answered yes.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct AnswerSet(pub u64);
+
+impl AnswerSet {
+    pub const EMPTY: AnswerSet = AnswerSet(0);
+
+    /// Questions `0` to `n - 1`, or all 64 when `n` is larger.
+    pub fn first(n: usize) -> Self {
+        if n >= 64 {
+            AnswerSet(u64::MAX)
+        } else {
+            AnswerSet((1 << n) - 1)
+        }
+    }
+
+    /// The set with question `i` added.
+    ///
+    /// # Panics
+    ///
+    /// If `i` is 64 or more, as a set only holds questions `0` to `63`.
+    pub fn with(self, i: usize) -> Self {
+        assert!(i < 64, "question {} does not fit in an answer set", i);
+        AnswerSet(self.0 | 1 << i)
+    }
+
+    /// Whether question `i` is in the set, which is never the case from 64 on.
+    pub fn contains(self, i: usize) -> bool {
+        i < 64 && self.0 >> i & 1 == 1
+    }
+
+    pub fn union(self, other: AnswerSet) -> Self {
+        AnswerSet(self.0 | other.0)
+    }
+
+    pub fn intersection(self, other: AnswerSet) -> Self {
+        AnswerSet(self.0 & other.0)
+    }
+
+    /// How many questions are in the set.
+    pub fn len(self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// The questions in the set, lowest first.
+    pub fn iter(self) -> impl Iterator<Item = usize> {
+        (0..64).filter(move |&i| self.contains(i))
+    }
+}
+
+impl BitOr for AnswerSet {
+    type Output = AnswerSet;
+
+    fn bitor(self, other: AnswerSet) -> AnswerSet {
+        self.union(other)
+    }
+}
+
+impl BitAnd for AnswerSet {
+    type Output = AnswerSet;
+
+    fn bitand(self, other: AnswerSet) -> AnswerSet {
+        self.intersection(other)
+    }
+}
+
+impl fmt::Display for AnswerSet {
+    /// Writes the set as puzzle letters, `a` for question 0.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for i in self.iter() {
+            match std::char::from_u32('a' as u32 + i as u32).filter(char::is_ascii_lowercase) {
+                Some(c) => write!(f, "{}", c)?,
+                None => write!(f, "[{}]", i)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The answers of one group, a set per person.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Group {
+    pub people: Vec<AnswerSet>,
+}
+
+impl Group {
+    /// The questions anyone answered yes to.
+    pub fn any(&self) -> AnswerSet {
+        self.people.iter().fold(AnswerSet::EMPTY, |a, &p| a | p)
+    }
+
+    /// The questions everyone answered yes to; none for an empty group.
+    pub fn all(&self) -> AnswerSet {
+        let mut people = self.people.iter().copied();
+        let first = people.next().unwrap_or_default();
+        people.fold(first, |a, p| a & p)
+    }
+
+    /// How the group answered the `questions` questions of its alphabet.
+    pub fn statistics(&self, questions: usize) -> Statistics {
+        let mut frequency = vec![0; questions];
+        for person in &self.people {
+            for i in person.iter() {
+                frequency[i] += 1;
+            }
+        }
+        Statistics {
+            people: self.people.len(),
+            frequency,
+        }
+    }
+
+    /// For each person, the questions nobody else in the group answered yes to.
+    pub fn unique(&self) -> Vec<AnswerSet> {
+        let once = self.statistics(64).answered_once();
+        self.people.iter().map(|&p| p & once).collect()
+    }
+}
+
+/// How a number of people answered a form.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Statistics {
+    pub people: usize,
+    /// How many people answered yes to each question, indexed by question.
+    pub frequency: Vec<usize>,
+}
+
+impl Statistics {
+    /// The questions at least `k` people answered yes to.
+    pub fn at_least(&self, k: usize) -> AnswerSet {
+        self.frequency
+            .iter()
+            .enumerate()
+            .filter(|&(_, &n)| n >= k)
+            .fold(AnswerSet::EMPTY, |set, (i, _)| set.with(i))
+    }
+
+    /// The questions more than half the people answered yes to.
+    pub fn majority(&self) -> AnswerSet {
+        self.at_least(self.people / 2 + 1)
+    }
+
+    /// The questions nobody answered yes to.
+    pub fn unanswered(&self) -> AnswerSet {
+        AnswerSet(!self.at_least(1).0 & AnswerSet::first(self.frequency.len()).0)
+    }
+
+    /// The questions exactly one person answered yes to.
+    pub fn answered_once(&self) -> AnswerSet {
+        self.at_least(1)
+            .intersection(AnswerSet(!self.at_least(2).0))
+    }
+}
+
+/// The statistics of every group of a survey and of everyone together.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct SurveyReport {
+    pub groups: Vec<Statistics>,
+    /// For each group, what each person answered that nobody else in the group did.
+    pub unique: Vec<Vec<AnswerSet>>,
+    /// Everyone in the survey counted as one group.
+    pub overall: Statistics,
+}
+
+impl SurveyReport {
+    /// Summarises `groups`, answered with the questions of `alphabet`.
+    pub fn new(groups: &[Group], alphabet: &Alphabet) -> Self {
+        let stats: Vec<Statistics> = groups
+            .iter()
+            .map(|g| g.statistics(alphabet.len()))
+            .collect();
+        let mut overall = Statistics {
+            people: 0,
+            frequency: vec![0; alphabet.len()],
+        };
+        for s in &stats {
+            overall.people += s.people;
+            for (total, n) in overall.frequency.iter_mut().zip(&s.frequency) {
+                *total += n;
+            }
+        }
+        SurveyReport {
+            groups: stats,
+            unique: groups.iter().map(Group::unique).collect(),
+            overall,
+        }
+    }
+
+    /// The sum over groups of the questions at least `k` people in the group answered yes to.
+    pub fn at_least_in_groups(&self, k: usize) -> usize {
+        self.groups.iter().map(|s| s.at_least(k).len()).sum()
+    }
+
+    /// The sum over groups of the questions a majority of the group answered yes to.
+    pub fn majority_in_groups(&self) -> usize {
+        self.groups.iter().map(|s| s.majority().len()).sum()
+    }
+
+    /// Writes the report as JSON, naming questions by their character in `alphabet`.
+    pub fn to_json(&self, alphabet: &Alphabet) -> Value {
+        let stats = |s: &Statistics| {
+            let frequency: serde_json::Map<String, Value> = s
+                .frequency
+                .iter()
+                .enumerate()
+                .map(|(i, &n)| (alphabet.symbol(i).to_string(), json!(n)))
+                .collect();
+            json!({
+                "people": s.people,
+                "frequency": frequency,
+                "any": alphabet.format(s.at_least(1)),
+                "all": alphabet.format(s.at_least(s.people.max(1))),
+                "majority": alphabet.format(s.majority()),
+                "unanswered": alphabet.format(s.unanswered()),
+                "answered_once": alphabet.format(s.answered_once()),
+            })
+        };
+        let groups: Vec<Value> = self
+            .groups
+            .iter()
+            .zip(&self.unique)
+            .map(|(s, unique)| {
+                let mut group = stats(s);
+                group["unique"] = unique.iter().map(|&u| alphabet.format(u)).collect();
+                group
+            })
+            .collect();
+        json!({"groups": groups, "overall": stats(&self.overall)})
+    }
+}
+
+/// Parses the customs forms into groups using the questions of `alphabet`; groups are separated
+/// by blank lines and each line is one person.
+pub fn parse_with(input: &str, alphabet: &Alphabet) -> Result<Vec<Group>, ParseError> {
+    records(input)
+        .into_iter()
+        .map(|(line, group)| {
+            let people = group
+                .lines()
+                .map(|l| {
+                    let l = l.trim_end();
+                    alphabet.answers(l).ok_or_else(|| {
+                        let (i, c) = l
+                            .char_indices()
+                            .find(|&(_, c)| alphabet.index(c).is_none())
+                            .unwrap();
+                        let part = &l[i..i + c.len_utf8()];
+                        ParseError::located(6, group, part, ParseErrorKind::UnexpectedToken)
+                    })
+                })
+                .collect::<Result<_, _>>()
+                .map_err(|e| e.offset(line))?;
+            Ok(Group { people })
         })
         .collect()
 }
 
+/// Parses the customs forms into groups, each a set of the questions `a` to `z` per person.
+#[aoc_generator(day6)]
+pub fn parse(input: &str) -> Result<Vec<Group>, ParseError> {
+    parse_with(input, &Alphabet::lowercase())
+}
+
+/// The sum over groups of the questions anyone in the group answered yes to.
 #[aoc(day6, part1)]
-fn answer_count(input: &Vec<Vec<Vec<char>>>) -> usize {
-    input
-        .iter()
-        .map(|group| {
-            group
-                .iter()
-                .flat_map(|v| v.iter().map(|c| *c).collect::<HashSet<char>>())
-                .collect::<HashSet<char>>()
-                .len()
-        })
-        .sum()
+pub fn part1(input: &[Group]) -> usize {
+    input.iter().map(|g| g.any().len()).sum()
 }
 
+/// The sum over groups of the questions everyone in the group answered yes to.
 #[aoc(day6, part2)]
-fn abs_answer_count(input: &Vec<Vec<Vec<char>>>) -> usize {
-    input
-        .iter()
-        .map(|group| {
-            group
-                .iter()
-                .map(|v| v.iter().map(|c| *c).collect::<HashSet<char>>())
-                .fold(Option::None, |l, g| {
-                    if l.is_none() {
-                        Some(g.clone())
-                    } else {
-                        Some(
-                            l.unwrap_or(HashSet::new())
-                                .intersection(&g)
-                                .map(|c| *c)
-                                .collect(),
-                        )
-                    }
-                })
-                .unwrap_or(HashSet::new())
-                .len()
-        })
-        .sum()
+pub fn part2(input: &[Group]) -> usize {
+    input.iter().map(|g| g.all().len()).sum()
 }
 
 #[cfg(test)]
@@ -73,13 +384,102 @@ a
 b";
     #[test]
     fn test_answered_yes_count() {
-        let input = to_vec(TEST_INPUT);
-        assert_eq!(answer_count(&input), 11);
+        let input = parse(TEST_INPUT).unwrap();
+        assert_eq!(part1(&input), 11);
     }
 
     #[test]
     fn test_abs_answered_yes_count() {
-        let input = to_vec(TEST_INPUT);
-        assert_eq!(abs_answer_count(&input), 6);
+        let input = parse(TEST_INPUT).unwrap();
+        assert_eq!(part2(&input), 6);
+    }
+
+    #[test]
+    fn test_bad_answer() {
+        let e = parse("abc\n\na\nb1").unwrap_err();
+        assert_eq!((e.line, e.column, e.text.as_str()), (4, 2, "1"));
+    }
+
+    #[test]
+    fn test_answer_set() {
+        let alphabet = Alphabet::lowercase();
+        let ab = alphabet.answers("ba").unwrap();
+        let ac = alphabet.answers("ac").unwrap();
+        assert_eq!(ab, AnswerSet(0b11));
+        assert_eq!((ab | ac).to_string(), "abc");
+        assert_eq!((ab & ac).to_string(), "a");
+        assert_eq!((ab | ac).len(), 3);
+        assert!(ab.contains(1) && !ab.contains(2));
+        assert!((ab & AnswerSet::EMPTY).is_empty());
+        assert_eq!(alphabet.all().len(), 26);
+        assert_eq!(alphabet.answers("aB"), None);
+        assert_eq!(Group::default().all(), AnswerSet::EMPTY);
+        assert_eq!(AnswerSet::first(0), AnswerSet::EMPTY);
+        assert_eq!(AnswerSet::first(64), AnswerSet(u64::MAX));
+        assert_eq!(AnswerSet::first(100), AnswerSet(u64::MAX));
+        assert!(AnswerSet(u64::MAX).with(63).contains(63));
+        assert!(!AnswerSet(u64::MAX).contains(64));
+    }
+
+    #[test]
+    fn test_alphabet() {
+        let alphabet = Alphabet::new("yn?é").unwrap();
+        assert_eq!(alphabet.len(), 4);
+        assert_eq!(alphabet.index('é'), Some(3));
+        assert_eq!(alphabet.index('a'), None);
+        let groups = parse_with("y?\nyé\n\nn", &alphabet).unwrap();
+        assert_eq!(alphabet.format(groups[0].any()), "y?é");
+        assert_eq!(alphabet.format(groups[0].all()), "y");
+        let e = parse_with("y\nyx", &alphabet).unwrap_err();
+        assert_eq!((e.line, e.column, e.text.as_str()), (2, 2, "x"));
+        assert_eq!(Alphabet::new("aba"), None);
+        assert_eq!(Alphabet::new(&"x".repeat(65)), None);
+        let wide: String = (0..64)
+            .map(|i| std::char::from_u32(0x100 + i).unwrap())
+            .collect();
+        assert_eq!(Alphabet::new(&wide).unwrap().all().len(), 64);
+        assert!(Alphabet::new("").unwrap().all().is_empty());
+    }
+
+    #[test]
+    fn test_statistics() {
+        let alphabet = Alphabet::lowercase();
+        let groups = parse("abc\nabd\nae\nz\n\nb\n\nxy\nyx").unwrap();
+        let stats = groups[0].statistics(alphabet.len());
+        assert_eq!(stats.people, 4);
+        assert_eq!(&stats.frequency[..6], &[3, 2, 1, 1, 1, 0]);
+        assert_eq!(stats.at_least(2).to_string(), "ab");
+        assert_eq!(stats.at_least(4), AnswerSet::EMPTY);
+        assert_eq!(stats.majority().to_string(), "a");
+        assert_eq!(stats.answered_once().to_string(), "cdez");
+        assert_eq!(stats.unanswered().len(), 26 - 6);
+        assert!(!stats.unanswered().contains(25));
+        let unique: Vec<String> = groups[0].unique().iter().map(|u| u.to_string()).collect();
+        assert_eq!(unique, vec!["c", "d", "e", "z"]);
+
+        let report = SurveyReport::new(&groups, &alphabet);
+        assert_eq!(report.overall.people, 7);
+        assert_eq!(report.overall.frequency[1], 3);
+        assert_eq!(report.at_least_in_groups(1), part1(&groups));
+        assert_eq!(report.majority_in_groups(), 1 + 1 + 2);
+        assert_eq!(report.unique[2], vec![AnswerSet::EMPTY; 2]);
+        let json = report.to_json(&alphabet);
+        assert_eq!(json["groups"][0]["unique"], json!(["c", "d", "e", "z"]));
+        assert_eq!(json["groups"][2]["all"], json!("xy"));
+        assert_eq!(json["overall"]["frequency"]["y"], json!(2));
+        assert_eq!(json["overall"]["answered_once"], json!("cdez"));
+    }
+
+    #[test]
+    fn test_statistics_match_parts() {
+        let groups = parse(TEST_INPUT).unwrap();
+        let report = SurveyReport::new(&groups, &Alphabet::lowercase());
+        let all: usize = report
+            .groups
+            .iter()
+            .map(|s| s.at_least(s.people).len())
+            .sum();
+        assert_eq!(report.at_least_in_groups(1), 11);
+        assert_eq!(all, 6);
     }
 }
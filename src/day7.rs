@@ -1,96 +1,553 @@
-use std::collections::HashMap;
+use crate::error::{missing, parse_lines, parse_number, ConfigError, ParseError, ParseErrorKind};
+use serde_json::{json, Value};
+use std::cell::OnceCell;
+use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
 
+/// One entry on the right of a rule: `num` bags of `bag_type` (singular, e.g. `shiny gold bag`).
 #[derive(Debug, Hash, Eq, PartialEq)]
-struct BagRule {
-    num: usize,
-    bag_type: String,
+pub struct BagRule {
+    pub num: usize,
+    pub bag_type: String,
 }
 
-impl BagRule {
-    fn contains_recur(&self, bag: &str, collection: &HashMap<String, Vec<BagRule>>) -> bool {
-        if self.bag_type == bag {
-            return true;
+impl FromStr for BagRule {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.find(' ') {
+            Some(n) => {
+                let num: usize = parse_number(7, s, &s[0..n])?;
+                Ok(BagRule {
+                    num,
+                    bag_type: String::from(s[n + 1..].trim_end_matches('s')),
+                })
+            }
+            // no bags
+            None => Err(missing(7, s, "bag type")),
         }
-        collection
-            .get(&self.bag_type)
-            .unwrap()
-            .iter()
-            .any(|br| br.contains_recur(bag, collection))
     }
+}
 
-    fn bag_count(&self, collection: &HashMap<String, Vec<BagRule>>, prev_count: usize) -> usize {
-        let rules = collection.get(&self.bag_type).unwrap();
-        if rules.is_empty() {
-            prev_count
-        } else {
-            rules
-                .iter()
-                .map(|br| br.bag_count(collection, br.num * prev_count))
-                .sum::<usize>()
-                + prev_count
+/// Something wrong with a set of bag rules.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RuleError {
+    /// `bag` has a second rule on `line`, after the one on line `first`, which is kept.
+    Duplicate {
+        bag: String,
+        line: usize,
+        first: usize,
+    },
+    /// The rule on `line` is the first to mention `bag`, which has no rule of its own.
+    Undefined { bag: String, line: usize },
+    /// The rule for `bag` on `line` says it directly contains itself.
+    SelfContaining { bag: String, line: usize },
+    /// Bags that contain each other in a loop, from a bag back round to the same bag.
+    Cycle(Vec<String>),
+    /// `bag` holds more bags than a `usize` can count.
+    Overflow(String),
+    /// No rule mentions the bag.
+    Unknown(String),
+}
+
+impl fmt::Display for RuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RuleError::Duplicate { bag, line, first } => write!(
+                f,
+                "line {}: `{}` was already defined on line {}, keeping the first",
+                line, bag, first
+            ),
+            RuleError::Undefined { bag, line } => {
+                write!(f, "line {}: `{}` has no rule", line, bag)
+            }
+            RuleError::SelfContaining { bag, line } => {
+                write!(f, "line {}: `{}` contains itself", line, bag)
+            }
+            RuleError::Cycle(path) => write!(f, "bags contain each other: {}", path.join(" -> ")),
+            RuleError::Overflow(bag) => write!(f, "`{}` holds too many bags to count", bag),
+            RuleError::Unknown(bag) => write!(f, "no rule mentions `{}`", bag),
         }
     }
 }
 
-impl From<&str> for BagRule {
-    fn from(s: &str) -> Self {
-        match s.find(" ") {
-            Some(n) => {
-                let num: usize = s[0..n].parse().unwrap();
-                BagRule {
+impl Error for RuleError {}
+
+/// Which part of the graph [`BagGraph::to_dot`] picks out.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Highlight<'a> {
+    /// The bag and everything it eventually contains.
+    From(&'a str),
+    /// The bag and everything that eventually contains it.
+    To(&'a str),
+}
+
+/// The rules as a graph over interned bag ids, answering containment questions about any bag.
+///
+/// Answers are cached per bag, so asking about the same bag twice never walks the graph again.
+/// Totals are shared between bags, so totalling every bag is linear in the size of the rules,
+/// but each bag's ancestors take a separate walk that may cover the whole graph.
+#[derive(Clone, Debug, Default)]
+pub struct BagGraph {
+    names: Vec<String>,
+    ids: HashMap<String, usize>,
+    /// The bags each bag directly contains, with how many of each.
+    contents: Vec<Vec<(usize, usize)>>,
+    /// The bags that directly contain each bag.
+    containers: Vec<Vec<usize>>,
+    /// The line of each bag's rule, if it has one.
+    lines: Vec<Option<usize>>,
+    /// Every rule for a bag that already had one, by bag and line.
+    duplicates: Vec<(usize, usize)>,
+    totals: Vec<OnceCell<Result<usize, RuleError>>>,
+    ancestors: Vec<OnceCell<Vec<usize>>>,
+}
+
+impl BagGraph {
+    /// Builds the graph from each bag's rules, taking the `n`th rule to be on line `n`. Bags that
+    /// are only ever contained get an id but no contents, and only the first rule for a bag is
+    /// used; [`BagGraph::validate`] reports both.
+    pub fn new<I: IntoIterator<Item = (String, Vec<BagRule>)>>(rules: I) -> BagGraph {
+        let mut graph = BagGraph::default();
+        for (line, (bag, rules)) in (1..).zip(rules) {
+            let outer = graph.intern(&bag);
+            if graph.lines[outer].is_some() {
+                graph.duplicates.push((outer, line));
+                continue;
+            }
+            graph.lines[outer] = Some(line);
+            for rule in rules {
+                let inner = graph.intern(&rule.bag_type);
+                graph.contents[outer].push((inner, rule.num));
+                graph.containers[inner].push(outer);
+            }
+        }
+        graph
+    }
+
+    fn intern(&mut self, bag: &str) -> usize {
+        if let Some(&id) = self.ids.get(bag) {
+            return id;
+        }
+        let id = self.names.len();
+        self.names.push(String::from(bag));
+        self.ids.insert(String::from(bag), id);
+        self.contents.push(vec![]);
+        self.containers.push(vec![]);
+        self.lines.push(None);
+        self.totals.push(OnceCell::new());
+        self.ancestors.push(OnceCell::new());
+        id
+    }
+
+    /// How many different bags the rules mention.
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Every bag, in the order the rules first mention them.
+    pub fn bags(&self) -> impl Iterator<Item = &str> {
+        self.names.iter().map(String::as_str)
+    }
+
+    /// The bags `bag` directly contains and how many of each, or `None` for an unknown bag.
+    pub fn contents(&self, bag: &str) -> Option<Vec<(&str, usize)>> {
+        let id = *self.ids.get(bag)?;
+        Some(
+            self.contents[id]
+                .iter()
+                .map(|&(inner, num)| (self.names[inner].as_str(), num))
+                .collect(),
+        )
+    }
+
+    /// Whether `outer` eventually holds at least one `inner` bag.
+    pub fn can_contain(&self, outer: &str, inner: &str) -> bool {
+        match (self.ids.get(outer), self.ids.get(inner)) {
+            (Some(&outer), Some(&inner)) => self.ancestor_ids(inner).binary_search(&outer).is_ok(),
+            _ => false,
+        }
+    }
+
+    /// Every bag that eventually holds at least one `bag`, in the order the rules first mention
+    /// them. Unknown bags have none.
+    pub fn ancestors(&self, bag: &str) -> Vec<&str> {
+        match self.ids.get(bag) {
+            Some(&id) => self
+                .ancestor_ids(id)
+                .iter()
+                .map(|&a| self.names[a].as_str())
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    /// How many bags a single `bag` holds in total, failing if it eventually contains itself
+    /// or holds too many bags to count.
+    pub fn total_inside(&self, bag: &str) -> Result<usize, RuleError> {
+        match self.ids.get(bag) {
+            Some(&id) => self.total(id),
+            None => Err(RuleError::Unknown(String::from(bag))),
+        }
+    }
+
+    /// Every problem with the rules: repeated and missing rules, then bags that contain
+    /// themselves directly, then each loop of bags found.
+    pub fn validate(&self) -> Vec<RuleError> {
+        let mut errors: Vec<RuleError> = self
+            .duplicates
+            .iter()
+            .map(|&(bag, line)| RuleError::Duplicate {
+                bag: self.names[bag].clone(),
+                line,
+                first: self.lines[bag].unwrap(),
+            })
+            .collect();
+        let mut by_line: Vec<(usize, usize)> = (0..self.len())
+            .filter_map(|bag| Some((self.lines[bag]?, bag)))
+            .collect();
+        by_line.sort_unstable();
+        let mut reported = vec![false; self.len()];
+        for &(line, bag) in &by_line {
+            for &(inner, _) in &self.contents[bag] {
+                if self.lines[inner].is_none() && !reported[inner] {
+                    reported[inner] = true;
+                    errors.push(RuleError::Undefined {
+                        bag: self.names[inner].clone(),
+                        line,
+                    });
+                }
+            }
+        }
+        for &(line, bag) in &by_line {
+            if self.contents[bag].iter().any(|&(inner, _)| inner == bag) {
+                errors.push(RuleError::SelfContaining {
+                    bag: self.names[bag].clone(),
+                    line,
+                });
+            }
+        }
+        errors.extend(self.cycles().into_iter().map(RuleError::Cycle));
+        errors
+    }
+
+    /// The rules as a Graphviz digraph, with an edge from each bag to each bag it directly
+    /// contains labelled with how many. `highlight` draws a bag and the part of the graph
+    /// reachable from or to it in red.
+    pub fn to_dot(&self, highlight: Option<Highlight<'_>>) -> String {
+        let marked = match highlight {
+            Some(highlight) => {
+                let (bag, inwards) = match highlight {
+                    Highlight::From(bag) => (bag, true),
+                    Highlight::To(bag) => (bag, false),
+                };
+                match self.ids.get(bag) {
+                    Some(&id) => {
+                        let mut marked = self.reachable(id, inwards);
+                        marked[id] = true;
+                        marked
+                    }
+                    None => vec![false; self.len()],
+                }
+            }
+            None => vec![false; self.len()],
+        };
+        let quote = |bag: usize| {
+            format!(
+                "\"{}\"",
+                self.names[bag].replace('\\', "\\\\").replace('"', "\\\"")
+            )
+        };
+        let mut out = String::from("digraph bags {\n");
+        for (bag, &highlighted) in marked.iter().enumerate() {
+            let style = if highlighted { " [color=red]" } else { "" };
+            out.push_str(&format!("    {}{};\n", quote(bag), style));
+        }
+        for (outer, contents) in self.contents.iter().enumerate() {
+            for &(inner, num) in contents {
+                let style = if marked[outer] && marked[inner] {
+                    ", color=red"
+                } else {
+                    ""
+                };
+                out.push_str(&format!(
+                    "    {} -> {} [label=\"{}\"{}];\n",
+                    quote(outer),
+                    quote(inner),
                     num,
-                    bag_type: String::from(s[n + 1..].trim_end_matches("s")),
+                    style
+                ));
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// The rules as a JSON object from each bag with a rule to an object of the bags it directly
+    /// contains and how many of each, e.g. `{"shiny gold bag": {"dark red bag": 2}}`.
+    pub fn to_json(&self) -> Value {
+        let bags: serde_json::Map<String, Value> = (0..self.len())
+            .filter(|&bag| self.lines[bag].is_some())
+            .map(|bag| {
+                let contents: serde_json::Map<String, Value> = self.contents[bag]
+                    .iter()
+                    .map(|&(inner, num)| (self.names[inner].clone(), json!(num)))
+                    .collect();
+                (self.names[bag].clone(), Value::Object(contents))
+            })
+            .collect();
+        Value::Object(bags)
+    }
+
+    /// Reads rules in the form written by [`BagGraph::to_json`], numbering them in order of bag
+    /// name as JSON objects are unordered.
+    pub fn from_json(text: &str) -> Result<BagGraph, ConfigError> {
+        let invalid = |message: String| ConfigError::new("bag rules", message);
+        let value: Value = serde_json::from_str(text).map_err(|e| invalid(e.to_string()))?;
+        let bags = value
+            .as_object()
+            .ok_or_else(|| invalid(String::from("expected an object of bags")))?;
+        let rules = bags
+            .iter()
+            .map(|(bag, contents)| {
+                let contents = contents.as_object().ok_or_else(|| {
+                    invalid(format!("expected an object of the bags inside `{}`", bag))
+                })?;
+                let rules = contents
+                    .iter()
+                    .map(|(inner, num)| {
+                        let num = num.as_u64().and_then(|n| usize::try_from(n).ok());
+                        Ok(BagRule {
+                            num: num.ok_or_else(|| {
+                                invalid(format!("`{}` in `{}` is not a count", inner, bag))
+                            })?,
+                            bag_type: inner.clone(),
+                        })
+                    })
+                    .collect::<Result<_, ConfigError>>()?;
+                Ok((bag.clone(), rules))
+            })
+            .collect::<Result<Vec<_>, ConfigError>>()?;
+        Ok(BagGraph::new(rules))
+    }
+
+    /// A loop of bags for every rule found closing one while walking the graph depth first,
+    /// leaving out bags that directly contain themselves.
+    fn cycles(&self) -> Vec<Vec<String>> {
+        #[derive(Copy, Clone, PartialEq)]
+        enum Visit {
+            New,
+            Open,
+            Done,
+        }
+        let mut visits = vec![Visit::New; self.len()];
+        let mut cycles = vec![];
+        for root in 0..self.len() {
+            if visits[root] != Visit::New {
+                continue;
+            }
+            visits[root] = Visit::Open;
+            // Each open bag with how many of its contents have been followed.
+            let mut path = vec![(root, 0)];
+            while let Some((bag, next)) = path.last_mut() {
+                let bag = *bag;
+                match self.contents[bag].get(*next) {
+                    Some(&(inner, _)) => {
+                        *next += 1;
+                        match visits[inner] {
+                            Visit::New => {
+                                visits[inner] = Visit::Open;
+                                path.push((inner, 0));
+                            }
+                            Visit::Open if inner != bag => {
+                                let start = path.iter().position(|&(b, _)| b == inner).unwrap();
+                                let mut cycle: Vec<String> = path[start..]
+                                    .iter()
+                                    .map(|&(b, _)| self.names[b].clone())
+                                    .collect();
+                                cycle.push(self.names[inner].clone());
+                                cycles.push(cycle);
+                            }
+                            _ => {}
+                        }
+                    }
+                    None => {
+                        visits[bag] = Visit::Done;
+                        path.pop();
+                    }
                 }
             }
-            // no bags
-            None => {
-                panic!("boom")
+        }
+        cycles
+    }
+
+    /// The sorted ids of the bags that eventually contain `id`.
+    fn ancestor_ids(&self, id: usize) -> &[usize] {
+        self.ancestors[id].get_or_init(|| {
+            let seen = self.reachable(id, false);
+            (0..self.len()).filter(|&b| seen[b]).collect()
+        })
+    }
+
+    /// Which bags can be reached from `id`, not counting `id` itself unless it is in a loop,
+    /// following rules from container to contents when `inwards` and the other way otherwise.
+    fn reachable(&self, id: usize, inwards: bool) -> Vec<bool> {
+        let mut seen = vec![false; self.len()];
+        let mut queue = vec![id];
+        while let Some(bag) = queue.pop() {
+            if inwards {
+                for &(b, _) in &self.contents[bag] {
+                    if !seen[b] {
+                        seen[b] = true;
+                        queue.push(b);
+                    }
+                }
+            } else {
+                for &b in &self.containers[bag] {
+                    if !seen[b] {
+                        seen[b] = true;
+                        queue.push(b);
+                    }
+                }
             }
         }
+        seen
+    }
+
+    /// The total number of bags inside `id`, filling in the totals of everything it contains
+    /// on the way without recursing. Only the bags not yet totalled are visited.
+    fn total(&self, id: usize) -> Result<usize, RuleError> {
+        if let Some(total) = self.totals[id].get() {
+            return total.clone();
+        }
+        let mut entered = HashSet::new();
+        // The bags entered but not yet totalled, each containing the next.
+        let mut path = vec![];
+        let mut stack = vec![id];
+        while let Some(&bag) = stack.last() {
+            if self.totals[bag].get().is_some() {
+                stack.pop();
+            } else if entered.insert(bag) {
+                path.push(bag);
+                for &(inner, _) in &self.contents[bag] {
+                    if self.totals[inner].get().is_some() {
+                        continue;
+                    }
+                    if entered.contains(&inner) {
+                        let start = path.iter().position(|&b| b == inner).unwrap();
+                        let mut cycle: Vec<String> = path[start..]
+                            .iter()
+                            .map(|&b| self.names[b].clone())
+                            .collect();
+                        cycle.push(self.names[inner].clone());
+                        let error = Err(RuleError::Cycle(cycle));
+                        for &b in &path {
+                            let _ = self.totals[b].set(error.clone());
+                        }
+                        return error;
+                    }
+                    stack.push(inner);
+                }
+            } else {
+                let total = self.contents[bag]
+                    .iter()
+                    .try_fold(0usize, |total, &(inner, num)| {
+                        let inside = self.totals[inner].get().unwrap().clone()?;
+                        inside
+                            .checked_add(1)
+                            .and_then(|n| n.checked_mul(num))
+                            .and_then(|n| n.checked_add(total))
+                            .ok_or_else(|| RuleError::Overflow(self.names[bag].clone()))
+                    });
+                let _ = self.totals[bag].set(total);
+                path.pop();
+                stack.pop();
+            }
+        }
+        self.totals[id].get().unwrap().clone()
     }
 }
 
+/// Parses each rule into the bag type it is for and the bags it must directly contain.
+pub fn parse_rules(input: &str) -> Result<Vec<(String, Vec<BagRule>)>, ParseError> {
+    parse_lines(input, |i| {
+        let mut splt = i.trim_end().splitn(2, " contain ");
+        let bag = splt.next().unwrap_or_default().trim_end_matches('s');
+        let unparsed_rules = splt
+            .next()
+            .ok_or_else(|| missing(7, i, "contained bags"))?
+            .trim_end_matches('.');
+        let rules: Vec<BagRule> = if unparsed_rules == "no other bags" {
+            vec![]
+        } else {
+            unparsed_rules
+                .split(", ")
+                .map(|s| s.parse().map_err(|e: ParseError| e.within(i, s)))
+                .collect::<Result<_, _>>()?
+        };
+        Ok((String::from(bag), rules))
+    })
+}
+
+/// Locates `error` on the line of the rule it concerns, pointing at the bag it names.
+fn locate(input: &str, graph: &BagGraph, error: &RuleError) -> ParseError {
+    let (bag, line, kind) = match error {
+        RuleError::Duplicate { bag, line, .. } => {
+            (bag, *line, ParseErrorKind::Invalid("repeated rule for"))
+        }
+        RuleError::Undefined { bag, line } => (bag, *line, ParseErrorKind::Missing("rule for")),
+        RuleError::SelfContaining { bag, line } => (
+            bag,
+            *line,
+            ParseErrorKind::Invalid("rule containing itself for"),
+        ),
+        RuleError::Cycle(path) => (
+            &path[0],
+            graph.lines[graph.ids[&path[0]]].unwrap(),
+            ParseErrorKind::Invalid("loop of rules through"),
+        ),
+        RuleError::Overflow(bag) => (
+            bag,
+            graph.lines[graph.ids[bag]].unwrap(),
+            ParseErrorKind::Invalid("count of bags inside"),
+        ),
+        RuleError::Unknown(bag) => (bag, 1, ParseErrorKind::Missing("rule for")),
+    };
+    let src = input.lines().nth(line - 1).unwrap_or_default();
+    let start = src.find(bag.as_str()).unwrap_or(0);
+    let part = &src[start..(start + bag.len()).min(src.len())];
+    ParseError::located(7, src, part, kind).offset(line - 1)
+}
+
+/// Parses the rules into a graph of which bags contain which, rejecting any rules
+/// [`BagGraph::validate`] finds a problem with and any bag holding too many bags to count.
 #[aoc_generator(day7)]
-fn to_hashmap(input: &str) -> HashMap<String, Vec<BagRule>> {
-    input
-        .lines()
-        .map(|i| {
-            let mut splt = i.split(" contain ");
-            let bag = splt.next().unwrap().trim_end_matches("s");
-            let unparsed_rules = splt.next().unwrap().trim_end_matches(".");
-            let rules: Vec<BagRule> = if unparsed_rules == "no other bags" {
-                vec![]
-            } else {
-                unparsed_rules.split(", ").map(|s| s.into()).collect()
-            };
-            (String::from(bag), rules)
-        })
-        .collect()
+pub fn parse(input: &str) -> Result<BagGraph, ParseError> {
+    let graph = BagGraph::new(parse_rules(input)?);
+    let overflow = graph.bags().find_map(|bag| graph.total_inside(bag).err());
+    match graph.validate().into_iter().chain(overflow).next() {
+        Some(error) => Err(locate(input, &graph, &error)),
+        None => Ok(graph),
+    }
 }
 
+/// How many bag types can eventually contain a shiny gold bag.
 #[aoc(day7, part1)]
-fn how_many_shiny_gold(input: &HashMap<String, Vec<BagRule>>) -> usize {
-    input
-        .iter()
-        .filter(|(bag, rules)| {
-            if bag.as_str() == "shiny gold bag" {
-                false
-            } else {
-                rules
-                    .iter()
-                    .any(|br| br.contains_recur("shiny gold bag", input))
-            }
-        })
-        .count()
+pub fn part1(input: &BagGraph) -> usize {
+    input.ancestors("shiny gold bag").len()
 }
 
+/// How many bags a single shiny gold bag holds in total, or 0 if no rule mentions one.
 #[aoc(day7, part2)]
-fn how_many_in_shiny_gold(input: &HashMap<String, Vec<BagRule>>) -> usize {
-    let rules = input.get("shiny gold bag").unwrap();
-    return rules
-        .iter()
-        .map(|br| br.bag_count(input, br.num))
-        .sum::<usize>();
+pub fn part2(input: &BagGraph) -> usize {
+    input.total_inside("shiny gold bag").unwrap_or(0)
 }
 
 #[cfg(test)]
@@ -116,15 +573,224 @@ dark blue bags contain 2 dark violet bags.
 dark violet bags contain no other bags.";
     #[test]
     fn test_how_many_bags() {
-        let input = to_hashmap(TEST_INPUT);
-        assert_eq!(how_many_shiny_gold(&input), 4);
+        let input = parse(TEST_INPUT).unwrap();
+        assert_eq!(part1(&input), 4);
     }
 
     #[test]
     fn test_how_many_in_shiny_recur() {
-        let input = to_hashmap(TEST_INPUT);
-        assert_eq!(how_many_in_shiny_gold(&input), 32);
-        let input = to_hashmap(OTHER_INPUT);
-        assert_eq!(how_many_in_shiny_gold(&input), 126);
+        let input = parse(TEST_INPUT).unwrap();
+        assert_eq!(part2(&input), 32);
+        let input = parse(OTHER_INPUT).unwrap();
+        assert_eq!(part2(&input), 126);
+    }
+
+    #[test]
+    fn test_bad_rule() {
+        let e = parse("shiny gold bags contain two dark red bags.").unwrap_err();
+        assert_eq!((e.line, e.column, e.text.as_str()), (1, 25, "two"));
+        let e = parse("faded blue bags contain no other bags.\ndark red bags").unwrap_err();
+        assert_eq!(e.line, 2);
+    }
+
+    #[test]
+    fn test_graph_queries() {
+        let graph = parse(TEST_INPUT).unwrap();
+        assert_eq!(graph.len(), 9);
+        assert!(graph.can_contain("light red bag", "faded blue bag"));
+        assert!(graph.can_contain("muted yellow bag", "shiny gold bag"));
+        assert!(!graph.can_contain("shiny gold bag", "bright white bag"));
+        assert!(!graph.can_contain("faded blue bag", "faded blue bag"));
+        assert!(!graph.can_contain("plaid bag", "faded blue bag"));
+        assert_eq!(
+            graph.ancestors("bright white bag"),
+            vec!["light red bag", "dark orange bag"]
+        );
+        assert!(graph.ancestors("light red bag").is_empty());
+        assert_eq!(graph.total_inside("dark olive bag"), Ok(7));
+        assert_eq!(graph.total_inside("light red bag"), Ok(186));
+        assert_eq!(graph.total_inside("faded blue bag"), Ok(0));
+        assert_eq!(
+            graph.total_inside("plaid bag"),
+            Err(RuleError::Unknown(String::from("plaid bag")))
+        );
+        assert!(graph.validate().is_empty());
+        assert_eq!(
+            graph.contents("muted yellow bag"),
+            Some(vec![("shiny gold bag", 2), ("faded blue bag", 9)])
+        );
+    }
+
+    #[test]
+    fn test_deep_rules() {
+        let input: String = (0..100_000)
+            .map(|i| format!("b{} bags contain 1 b{} bag.\n", i, i + 1))
+            .collect();
+        let graph = parse(&(input + "b100000 bags contain no other bags.")).unwrap();
+        assert_eq!(graph.total_inside("b0 bag"), Ok(100_000));
+        assert_eq!(graph.ancestors("b100000 bag").len(), 100_000);
+        assert!(graph.can_contain("b0 bag", "b99999 bag"));
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        let graph = BagGraph::new(
+            parse_rules(
+                "red bags contain 1 blue bag.
+blue bags contain 2 green bags, 1 tan bag.
+green bags contain 1 red bag.
+tan bags contain 1 tan bag.
+red bags contain no other bags.
+shiny gold bags contain 3 plum bags, 1 grey bag.
+grey bags contain no other bags.",
+            )
+            .unwrap(),
+        );
+        let cycle = RuleError::Cycle(
+            ["red bag", "blue bag", "green bag", "red bag"]
+                .iter()
+                .map(|&b| String::from(b))
+                .collect(),
+        );
+        assert_eq!(
+            graph.validate(),
+            vec![
+                RuleError::Duplicate {
+                    bag: String::from("red bag"),
+                    line: 5,
+                    first: 1
+                },
+                RuleError::Undefined {
+                    bag: String::from("plum bag"),
+                    line: 6
+                },
+                RuleError::SelfContaining {
+                    bag: String::from("tan bag"),
+                    line: 4
+                },
+                cycle,
+            ]
+        );
+        assert!(matches!(
+            graph.total_inside("green bag"),
+            Err(RuleError::Cycle(_))
+        ));
+        assert_eq!(
+            graph.total_inside("tan bag"),
+            Err(RuleError::Cycle(vec![
+                String::from("tan bag"),
+                String::from("tan bag")
+            ]))
+        );
+        assert_eq!(graph.total_inside("shiny gold bag"), Ok(4));
+        assert_eq!(part2(&graph), 4);
+        assert!(graph.can_contain("blue bag", "blue bag"));
+        assert_eq!(
+            graph.validate()[1].to_string(),
+            "line 6: `plum bag` has no rule"
+        );
+    }
+
+    #[test]
+    fn test_total_overflow() {
+        let input: String = (0..70)
+            .map(|i| format!("b{} bags contain 2 b{} bags.\n", i, i + 1))
+            .collect();
+        let graph = BagGraph::new(parse_rules(&input).unwrap());
+        assert_eq!(
+            graph.total_inside("b0 bag"),
+            Err(RuleError::Overflow(String::from("b6 bag")))
+        );
+        assert_eq!(graph.total_inside("b10 bag"), Ok((1 << 61) - 2));
+        let input = input + "b70 bags contain no other bags.\n";
+        let e = parse(&input).unwrap_err();
+        assert_eq!((e.line, e.column, e.text.as_str()), (7, 1, "b6 bag"));
+        assert_eq!(e.kind, ParseErrorKind::Invalid("count of bags inside"));
+    }
+
+    #[test]
+    fn test_rejected_rules() {
+        let located = |input: &str| {
+            let e = parse(input).unwrap_err();
+            (e.line, e.column, e.text, e.kind)
+        };
+        assert_eq!(
+            located("a bags contain 1 b bag.\nb bags contain 2 a bags."),
+            (
+                1,
+                1,
+                String::from("a bag"),
+                ParseErrorKind::Invalid("loop of rules through")
+            )
+        );
+        assert_eq!(
+            located("a bags contain no other bags.\nc bags contain 1 c bag."),
+            (
+                2,
+                1,
+                String::from("c bag"),
+                ParseErrorKind::Invalid("rule containing itself for")
+            )
+        );
+        assert_eq!(
+            located("a bags contain 3 b bags."),
+            (
+                1,
+                18,
+                String::from("b bag"),
+                ParseErrorKind::Missing("rule for")
+            )
+        );
+        assert_eq!(
+            located("a bags contain no other bags.\na bags contain no other bags."),
+            (
+                2,
+                1,
+                String::from("a bag"),
+                ParseErrorKind::Invalid("repeated rule for")
+            )
+        );
+    }
+
+    #[test]
+    fn test_dot() {
+        let graph = parse(OTHER_INPUT).unwrap();
+        let dot = graph.to_dot(None);
+        assert!(dot.starts_with("digraph bags {\n    \"shiny gold bag\";\n"));
+        assert!(dot.contains("    \"shiny gold bag\" -> \"dark red bag\" [label=\"2\"];\n"));
+        assert!(!dot.contains("red]"));
+        let dot = graph.to_dot(Some(Highlight::To("dark orange bag")));
+        assert!(dot.contains("    \"dark red bag\" [color=red];\n"));
+        assert!(dot.contains("    \"dark yellow bag\";\n"));
+        assert!(dot.contains("\"dark red bag\" -> \"dark orange bag\" [label=\"2\", color=red];"));
+        assert!(dot.contains("\"dark orange bag\" -> \"dark yellow bag\" [label=\"2\"];"));
+        let dot = graph.to_dot(Some(Highlight::From("dark blue bag")));
+        assert!(dot.contains("\"dark blue bag\" -> \"dark violet bag\" [label=\"2\", color=red];"));
+        assert_eq!(dot.matches("[color=red]").count(), 2);
+        let quoted = BagGraph::new(vec![(String::from("a \"b\" bag"), vec![])]);
+        assert!(quoted.to_dot(None).contains("    \"a \\\"b\\\" bag\";\n"));
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let graph = parse(TEST_INPUT).unwrap();
+        let json = graph.to_json();
+        assert_eq!(
+            json["muted yellow bag"],
+            json!({"shiny gold bag": 2, "faded blue bag": 9})
+        );
+        assert_eq!(json["faded blue bag"], json!({}));
+        let imported = BagGraph::from_json(&json.to_string()).unwrap();
+        assert_eq!(imported.to_json(), json);
+        assert_eq!(part1(&imported), 4);
+        assert_eq!(part2(&imported), 32);
+
+        let e = BagGraph::from_json(r#"{"a bag": {"b bag": -1}}"#).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "invalid bag rules: `b bag` in `a bag` is not a count"
+        );
+        assert!(BagGraph::from_json("[]").is_err());
+        assert!(BagGraph::from_json(r#"{"a bag": 3}"#).is_err());
     }
 }
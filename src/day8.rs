@@ -1,51 +1,72 @@
+use crate::error::{missing, parse_lines, parse_number, ParseError, ParseErrorKind};
 use std::collections::HashSet;
+use std::str::FromStr;
 
+/// A boot code operation.
 #[derive(Debug, PartialOrd, PartialEq, Clone)]
-enum ActionType {
+pub enum ActionType {
     Acc,
     Jmp,
     Nop,
 }
 
-impl From<&str> for ActionType {
-    fn from(s: &str) -> Self {
+impl FromStr for ActionType {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
-            "acc" => ActionType::Acc,
-            "jmp" => ActionType::Jmp,
-            "nop" => ActionType::Nop,
-            _ => unimplemented!(),
+            "acc" => Ok(ActionType::Acc),
+            "jmp" => Ok(ActionType::Jmp),
+            "nop" => Ok(ActionType::Nop),
+            _ => Err(ParseError::located(
+                8,
+                s,
+                s,
+                ParseErrorKind::UnexpectedToken,
+            )),
         }
     }
 }
 
+/// One instruction of the boot code: an operation and its signed argument.
 #[derive(Debug, PartialOrd, PartialEq, Clone)]
-struct Action {
-    num: i32,
-    action: ActionType,
+pub struct Action {
+    pub num: i32,
+    pub action: ActionType,
 }
 
-impl From<&str> for Action {
-    fn from(s: &str) -> Self {
-        let mut splt = s.trim().split(" ");
-        let action: ActionType = splt.next().unwrap().into();
-        let num: i32 = splt.next().unwrap().parse().unwrap();
-        Action { num, action }
+impl FromStr for Action {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut splt = s.trim().split(' ');
+        let action_str = splt.next().unwrap_or_default();
+        let action: ActionType = action_str
+            .parse()
+            .map_err(|e: ParseError| e.within(s, action_str))?;
+        let num: i32 = splt
+            .next()
+            .ok_or_else(|| missing(8, s, "argument"))
+            .and_then(|n| parse_number(8, s, n))?;
+        Ok(Action { num, action })
     }
 }
 
 impl Action {
-    fn run(&self, pos: &i32, accumulator: &i32) -> (i32, i32) {
+    /// Executes the instruction at `pos`, returning the next position and accumulator.
+    pub fn run(&self, pos: &i32, accumulator: &i32) -> (i32, i32) {
         match self.action {
             ActionType::Acc => (pos + 1, accumulator + self.num),
-            ActionType::Jmp => (pos + self.num, accumulator.clone()),
-            ActionType::Nop => (pos + 1, accumulator.clone()),
+            ActionType::Jmp => (pos + self.num, *accumulator),
+            ActionType::Nop => (pos + 1, *accumulator),
         }
     }
 }
 
+/// Parses the boot code, one instruction per line.
 #[aoc_generator(day8)]
-fn to_vec(input: &str) -> Vec<Action> {
-    input.lines().map(|i| i.into()).collect()
+pub fn parse(input: &str) -> Result<Vec<Action>, ParseError> {
+    parse_lines(input, str::parse)
 }
 
 fn run_actions(input: &[Action]) -> (i32, bool, Vec<usize>) {
@@ -69,14 +90,17 @@ fn run_actions(input: &[Action]) -> (i32, bool, Vec<usize>) {
     }
 }
 
+/// The accumulator just before any instruction would run a second time.
 #[aoc(day8, part1)]
-fn last_value_before_rerun(input: &Vec<Action>) -> i32 {
-    run_actions(input.as_slice()).0
+pub fn part1(input: &[Action]) -> i32 {
+    run_actions(input).0
 }
 
+/// The accumulator after the program terminates once the single corrupted `jmp`/`nop` is
+/// swapped, or 0 if no swap makes it terminate.
 #[aoc(day8, part2)]
-fn fix_program(input: &Vec<Action>) -> i32 {
-    let (_, _, mut action_order) = run_actions(input.as_slice());
+pub fn part2(input: &[Action]) -> i32 {
+    let (_, _, mut action_order) = run_actions(input);
     action_order.reverse();
     for action in action_order
         .iter()
@@ -122,13 +146,21 @@ acc +6";
 
     #[test]
     fn test_last_action() {
-        let input = to_vec(TEST_INPUT);
-        assert_eq!(last_value_before_rerun(&input), 5);
+        let input = parse(TEST_INPUT).unwrap();
+        assert_eq!(part1(&input), 5);
     }
 
     #[test]
     fn test_fix_run() {
-        let input = to_vec(TEST_INPUT);
-        assert_eq!(fix_program(&input), 8);
+        let input = parse(TEST_INPUT).unwrap();
+        assert_eq!(part2(&input), 8);
+    }
+
+    #[test]
+    fn test_bad_action() {
+        let e = parse("nop +0\nacc +1\njump +4").unwrap_err();
+        assert_eq!((e.line, e.column, e.text.as_str()), (3, 1, "jump"));
+        let e = parse("nop +0\nacc one").unwrap_err();
+        assert_eq!((e.line, e.column, e.text.as_str()), (2, 5, "one"));
     }
 }
@@ -1,6 +1,9 @@
+use crate::error::{parse_lines, parse_number, ParseError};
+
+/// Parses the XMAS data, one number per line.
 #[aoc_generator(day9)]
-fn to_vec(input: &str) -> Vec<usize> {
-    input.lines().map(|i| i.parse().unwrap()).collect()
+pub fn parse(input: &str) -> Result<Vec<usize>, ParseError> {
+    parse_lines(input, |l| parse_number(9, l, l.trim()))
 }
 
 fn two_number_sum(desired_sum: &usize, preamble: &usize, numbers: &[usize]) -> bool {
@@ -14,19 +17,22 @@ fn two_number_sum(desired_sum: &usize, preamble: &usize, numbers: &[usize]) -> b
             }
         }
     }
-    return false;
+    false
 }
 
-fn first_without_sum(preamble: &usize, input: &[usize]) -> usize {
-    for i in 0..input.len() - preamble {
-        if !two_number_sum(&input[i + preamble].clone(), &preamble, &input[i..]) {
+/// The first number that is not the sum of two of the `preamble` numbers before it, or 0.
+pub fn first_without_sum(preamble: &usize, input: &[usize]) -> usize {
+    for i in 0..input.len().saturating_sub(*preamble) {
+        if !two_number_sum(&input[i + preamble].clone(), preamble, &input[i..]) {
             return input[i + preamble];
         }
     }
-    return 0;
+    0
 }
 
-fn contiguous_set_sum(val: &usize, input: &[usize]) -> usize {
+/// The sum of the smallest and largest number of the first contiguous run (of at least two
+/// numbers) that sums to `val`, or 0.
+pub fn contiguous_set_sum(val: &usize, input: &[usize]) -> usize {
     for (i, v) in input.iter().enumerate() {
         let mut sum = *v;
         let mut vals = vec![v];
@@ -41,18 +47,20 @@ fn contiguous_set_sum(val: &usize, input: &[usize]) -> usize {
             }
         }
     }
-    return 0;
+    0
 }
 
+/// The first number that is not the sum of two of the 25 numbers before it.
 #[aoc(day9, part1)]
-fn last_value_before_rerun(input: &Vec<usize>) -> usize {
-    first_without_sum(&25, &input[..])
+pub fn part1(input: &[usize]) -> usize {
+    first_without_sum(&25, input)
 }
 
+/// The sum of the smallest and largest number in the contiguous run summing to the part 1 answer.
 #[aoc(day9, part2)]
-fn code_break(input: &Vec<usize>) -> usize {
-    let desired_sum = first_without_sum(&25, &input[..]);
-    contiguous_set_sum(&desired_sum, &input[..])
+pub fn part2(input: &[usize]) -> usize {
+    let desired_sum = first_without_sum(&25, input);
+    contiguous_set_sum(&desired_sum, input)
 }
 
 #[cfg(test)]
@@ -82,13 +90,13 @@ mod tests {
 
     #[test]
     fn test_first_without_sum() {
-        let input = to_vec(TEST_INPUT);
+        let input = parse(TEST_INPUT).unwrap();
         assert_eq!(first_without_sum(&5, &input[..]), 127);
     }
 
     #[test]
     fn test_contiguous_set_sum() {
-        let input = to_vec(TEST_INPUT);
+        let input = parse(TEST_INPUT).unwrap();
         assert_eq!(contiguous_set_sum(&127, &input[..]), 62);
     }
 }
@@ -0,0 +1,201 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// What went wrong while parsing a puzzle input.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ParseErrorKind {
+    /// The text was expected to be a number.
+    InvalidNumber,
+    /// The text was not one of the tokens allowed at this position.
+    UnexpectedToken,
+    /// A required part of the input was not present.
+    Missing(&'static str),
+    /// The text was well formed but its value is not allowed, e.g. an empty range.
+    Invalid(&'static str),
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseErrorKind::InvalidNumber => write!(f, "invalid number"),
+            ParseErrorKind::UnexpectedToken => write!(f, "unexpected token"),
+            ParseErrorKind::Missing(what) => write!(f, "missing {}", what),
+            ParseErrorKind::Invalid(what) => write!(f, "invalid {}", what),
+        }
+    }
+}
+
+/// A malformed puzzle input, located by day, line and column (both 1-based).
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ParseError {
+    pub day: u8,
+    pub line: usize,
+    pub column: usize,
+    pub text: String,
+    pub kind: ParseErrorKind,
+}
+
+impl ParseError {
+    /// Builds an error for `part`, which must be a sub-slice of `src`; the line and column are
+    /// computed relative to the start of `src`.
+    pub fn located(day: u8, src: &str, part: &str, kind: ParseErrorKind) -> Self {
+        let offset = (part.as_ptr() as usize)
+            .checked_sub(src.as_ptr() as usize)
+            .filter(|&o| o <= src.len() && src.is_char_boundary(o))
+            .unwrap_or(0);
+        let before = &src[..offset];
+        let line = before.matches('\n').count() + 1;
+        let column = match before.rfind('\n') {
+            Some(n) => before[n + 1..].chars().count() + 1,
+            None => before.chars().count() + 1,
+        };
+        ParseError {
+            day,
+            line,
+            column,
+            text: String::from(part),
+            kind,
+        }
+    }
+
+    /// Shifts the error down by `lines`, used when `src` started part way through the input.
+    pub fn offset(mut self, lines: usize) -> Self {
+        self.line += lines;
+        self
+    }
+
+    /// Relocates an error found while parsing `part` so it is relative to `src`, which `part`
+    /// must be a sub-slice of.
+    pub fn within(mut self, src: &str, part: &str) -> Self {
+        let start = ParseError::located(self.day, src, part, self.kind.clone());
+        if self.line == 1 {
+            self.column += start.column - 1;
+        }
+        self.line += start.line - 1;
+        self
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "day {} line {} column {}: {} `{}`",
+            self.day, self.line, self.column, self.kind, self.text
+        )
+    }
+}
+
+impl Error for ParseError {}
+
+/// A configuration file, such as an answer manifest or password policy, that could not be read.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ConfigError {
+    /// What kind of file it was, e.g. `manifest`.
+    pub file: &'static str,
+    pub message: String,
+}
+
+impl ConfigError {
+    pub fn new(file: &'static str, message: String) -> Self {
+        ConfigError { file, message }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid {}: {}", self.file, self.message)
+    }
+}
+
+impl Error for ConfigError {}
+
+/// Parses `part` (a sub-slice of `src`) as a number, locating any failure within `src`.
+pub(crate) fn parse_number<T: FromStr>(day: u8, src: &str, part: &str) -> Result<T, ParseError> {
+    part.parse()
+        .map_err(|_| ParseError::located(day, src, part, ParseErrorKind::InvalidNumber))
+}
+
+/// The error for a required piece of `src` that is absent, pointing at the end of `src`.
+pub(crate) fn missing(day: u8, src: &str, what: &'static str) -> ParseError {
+    ParseError::located(day, src, &src[src.len()..], ParseErrorKind::Missing(what))
+}
+
+/// Parses every line of `input` with `parse`, fixing up line numbers of any error.
+pub(crate) fn parse_lines<T, F>(input: &str, parse: F) -> Result<Vec<T>, ParseError>
+where
+    F: Fn(&str) -> Result<T, ParseError>,
+{
+    input
+        .lines()
+        .enumerate()
+        .map(|(i, l)| parse(l).map_err(|e| e.offset(i)))
+        .collect()
+}
+
+/// Parses each character of `line` as a `T`, locating any failure within the line.
+pub(crate) fn parse_chars<T>(line: &str) -> Result<Vec<T>, ParseError>
+where
+    T: FromStr<Err = ParseError>,
+{
+    line.char_indices()
+        .map(|(i, c)| {
+            let cell = &line[i..i + c.len_utf8()];
+            cell.parse().map_err(|e: ParseError| e.within(line, cell))
+        })
+        .collect()
+}
+
+/// Splits `input` into blocks separated by blank lines, paired with the index of the block's
+/// first line.
+pub(crate) fn records(input: &str) -> Vec<(usize, &str)> {
+    let mut records = vec![];
+    let mut start = None;
+    let mut end = 0;
+    for (i, line) in input.lines().enumerate() {
+        let offset = line.as_ptr() as usize - input.as_ptr() as usize;
+        if line.trim().is_empty() {
+            if let Some((first, from)) = start.take() {
+                records.push((first, &input[from..end]));
+            }
+        } else {
+            if start.is_none() {
+                start = Some((i, offset));
+            }
+            end = offset + line.len();
+        }
+    }
+    if let Some((first, from)) = start {
+        records.push((first, &input[from..end]));
+    }
+    records
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_located() {
+        let src = "abc\nde fg\nh";
+        let e = ParseError::located(1, src, &src[7..9], ParseErrorKind::UnexpectedToken);
+        assert_eq!((e.line, e.column, e.text.as_str()), (2, 4, "fg"));
+        assert_eq!(
+            e.to_string(),
+            "day 1 line 2 column 4: unexpected token `fg`"
+        );
+    }
+
+    #[test]
+    fn test_parse_lines_offsets() {
+        let e = parse_lines("1\n2\nx", |l| parse_number::<usize>(1, l, l)).unwrap_err();
+        assert_eq!((e.line, e.column), (3, 1));
+    }
+
+    #[test]
+    fn test_records() {
+        let input = "a\nb\n\n\nc\n";
+        assert_eq!(records(input), vec![(0, "a\nb"), (4, "c")]);
+    }
+}
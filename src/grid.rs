@@ -0,0 +1,314 @@
+use crate::error::{parse_chars, parse_lines, ParseError, ParseErrorKind};
+use bitvec::prelude::*;
+use std::ops::{Index, IndexMut};
+use std::str::FromStr;
+
+/// The offsets of the eight squares around a square.
+pub const DIRECTIONS: [(isize, isize); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+/// The in-bounds squares around `(x, y)` of a `width` by `height` grid.
+fn neighbours(
+    width: usize,
+    height: usize,
+    x: usize,
+    y: usize,
+) -> impl Iterator<Item = (usize, usize)> {
+    DIRECTIONS.iter().filter_map(move |&(dx, dy)| {
+        let (nx, ny) = (x.checked_add_signed(dx)?, y.checked_add_signed(dy)?);
+        if nx < width && ny < height {
+            Some((nx, ny))
+        } else {
+            None
+        }
+    })
+}
+
+/// Parses `input` one row per line, checking every row is as wide as the first.
+fn parse_rows<T, F>(day: u8, input: &str, parse: F) -> Result<(usize, Vec<Vec<T>>), ParseError>
+where
+    F: Fn(&str) -> Result<Vec<T>, ParseError>,
+{
+    let rows = parse_lines(input, parse)?;
+    let width = rows.first().map_or(0, Vec::len);
+    if let Some((i, _)) = rows
+        .iter()
+        .enumerate()
+        .find(|(_, row)| row.is_empty() || row.len() != width)
+    {
+        let line = input.lines().nth(i).unwrap_or_default();
+        return Err(
+            ParseError::located(day, line, line, ParseErrorKind::Invalid("row width")).offset(i),
+        );
+    }
+    Ok((width, rows))
+}
+
+/// A rectangular grid of squares stored row by row, indexed by `(x, y)` from the top left.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    /// A `width` by `height` grid with every square set to `value`.
+    pub fn new(width: usize, height: usize, value: T) -> Self
+    where
+        T: Clone,
+    {
+        Grid {
+            width,
+            height,
+            cells: vec![value; width * height],
+        }
+    }
+
+    /// Parses a grid with one row per line and one `T` per character, such as the `#`, `.` and
+    /// `L` of a seat layout. Every row must be as wide as the first.
+    pub fn parse(day: u8, input: &str) -> Result<Self, ParseError>
+    where
+        T: FromStr<Err = ParseError>,
+    {
+        let (width, rows) = parse_rows(day, input, parse_chars)?;
+        Ok(Grid {
+            width,
+            height: rows.len(),
+            cells: rows.into_iter().flatten().collect(),
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The square at `(x, y)`, or `None` outside the grid.
+    pub fn get(&self, x: isize, y: isize) -> Option<&T> {
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return None;
+        }
+        self.cells.get(y as usize * self.width + x as usize)
+    }
+
+    /// The square at `(x, y)` of the grid repeated endlessly in every direction.
+    pub fn get_wrapping(&self, x: isize, y: isize) -> &T {
+        let x = x.rem_euclid(self.width as isize) as usize;
+        let y = y.rem_euclid(self.height as isize) as usize;
+        &self[(x, y)]
+    }
+
+    /// The in-bounds squares around `(x, y)`.
+    pub fn neighbours(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> {
+        neighbours(self.width, self.height, x, y)
+    }
+
+    /// The rows of the grid from the top.
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.cells.chunks(self.width.max(1))
+    }
+
+    /// Every square with its `(x, y)`, row by row.
+    pub fn iter(&self) -> impl Iterator<Item = ((usize, usize), &T)> {
+        let width = self.width;
+        self.cells
+            .iter()
+            .enumerate()
+            .map(move |(i, cell)| ((i % width, i / width), cell))
+    }
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, (x, y): (usize, usize)) -> &T {
+        assert!(x < self.width, "x {} outside grid width {}", x, self.width);
+        &self.cells[y * self.width + x]
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut T {
+        assert!(x < self.width, "x {} outside grid width {}", x, self.width);
+        &mut self.cells[y * self.width + x]
+    }
+}
+
+/// A grid of squares that are either set or not, packed one bit per square.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct BitGrid {
+    width: usize,
+    height: usize,
+    bits: BitVec,
+}
+
+impl BitGrid {
+    /// A `width` by `height` grid with no squares set.
+    pub fn new(width: usize, height: usize) -> Self {
+        BitGrid {
+            width,
+            height,
+            bits: BitVec::repeat(false, width * height),
+        }
+    }
+
+    /// Parses a grid with one row per line where `#` is set and `.` is not. Every row must be as
+    /// wide as the first.
+    pub fn parse(day: u8, input: &str) -> Result<Self, ParseError> {
+        let (width, rows) = parse_rows(day, input, |line| {
+            line.char_indices()
+                .map(|(i, c)| match c {
+                    '#' => Ok(true),
+                    '.' => Ok(false),
+                    _ => Err(ParseError::located(
+                        day,
+                        line,
+                        &line[i..i + c.len_utf8()],
+                        ParseErrorKind::UnexpectedToken,
+                    )),
+                })
+                .collect()
+        })?;
+        let mut grid = BitGrid::new(width, rows.len());
+        for (y, row) in rows.iter().enumerate() {
+            for (x, &set) in row.iter().enumerate() {
+                grid.set(x, y, set);
+            }
+        }
+        Ok(grid)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Whether `(x, y)` is set, or `None` outside the grid.
+    pub fn get(&self, x: isize, y: isize) -> Option<bool> {
+        if x < 0 || y < 0 || x as usize >= self.width || y as usize >= self.height {
+            return None;
+        }
+        Some(self.bits[y as usize * self.width + x as usize])
+    }
+
+    /// Whether `(x, y)` is set in the grid repeated endlessly in every direction.
+    pub fn get_wrapping(&self, x: isize, y: isize) -> bool {
+        let x = x.rem_euclid(self.width as isize) as usize;
+        let y = y.rem_euclid(self.height as isize) as usize;
+        self.bits[y * self.width + x]
+    }
+
+    /// Sets or clears `(x, y)`, which must be inside the grid.
+    pub fn set(&mut self, x: usize, y: usize, value: bool) {
+        assert!(
+            x < self.width && y < self.height,
+            "({}, {}) outside {}x{} grid",
+            x,
+            y,
+            self.width,
+            self.height
+        );
+        self.bits.set(y * self.width + x, value);
+    }
+
+    /// The in-bounds squares around `(x, y)`.
+    pub fn neighbours(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> {
+        neighbours(self.width, self.height, x, y)
+    }
+
+    /// How many squares are set.
+    pub fn count_ones(&self) -> usize {
+        self.bits.count_ones()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Copy, Clone, Eq, PartialEq)]
+    enum Square {
+        Seat,
+        Floor,
+    }
+
+    impl FromStr for Square {
+        type Err = ParseError;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            match s {
+                "L" => Ok(Square::Seat),
+                "." => Ok(Square::Floor),
+                _ => Err(ParseError::located(
+                    0,
+                    s,
+                    s,
+                    ParseErrorKind::UnexpectedToken,
+                )),
+            }
+        }
+    }
+
+    #[test]
+    fn test_grid() {
+        let mut grid: Grid<Square> = Grid::parse(0, "L.L\n.LL").unwrap();
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(grid[(1, 1)], Square::Seat);
+        assert_eq!(grid.get(2, 0), Some(&Square::Seat));
+        assert_eq!(grid.get(3, 0), None);
+        assert_eq!(grid.get(-1, 0), None);
+        assert_eq!(grid.get_wrapping(-2, 3), &Square::Seat);
+        grid[(1, 0)] = Square::Seat;
+        assert_eq!(grid.rows().next(), Some(&[Square::Seat; 3][..]));
+        assert_eq!(grid.iter().filter(|&(_, s)| *s == Square::Floor).count(), 1);
+        assert_eq!(grid.iter().nth(4).unwrap().0, (1, 1));
+
+        let e = Grid::<Square>::parse(0, "L.\n.#").unwrap_err();
+        assert_eq!((e.line, e.column, e.text.as_str()), (2, 2, "#"));
+        let e = Grid::<Square>::parse(0, "L.\n.").unwrap_err();
+        assert_eq!((e.line, e.kind), (2, ParseErrorKind::Invalid("row width")));
+    }
+
+    #[test]
+    fn test_neighbours() {
+        let grid = Grid::new(3, 3, 0);
+        assert_eq!(grid.neighbours(1, 1).count(), 8);
+        assert_eq!(
+            grid.neighbours(0, 0).collect::<Vec<_>>(),
+            vec![(1, 0), (0, 1), (1, 1)]
+        );
+        assert_eq!(grid.neighbours(2, 1).count(), 5);
+    }
+
+    #[test]
+    fn test_bit_grid() {
+        let mut grid = BitGrid::parse(3, "#..\n.#.").unwrap();
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(grid.get(0, 0), Some(true));
+        assert_eq!(grid.get(1, 0), Some(false));
+        assert_eq!(grid.get(0, 2), None);
+        assert!(grid.get_wrapping(-2, -1));
+        grid.set(2, 1, true);
+        assert_eq!(grid.count_ones(), 3);
+        assert_eq!(grid.neighbours(2, 1).count(), 3);
+
+        let e = BitGrid::parse(3, "#.\n.L").unwrap_err();
+        assert_eq!((e.day, e.line, e.column, e.text.as_str()), (3, 2, 2, "L"));
+        assert!(BitGrid::parse(3, "#.\n\n").is_err());
+    }
+}
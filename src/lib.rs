@@ -1,10 +1,37 @@
+//! Solutions to [Advent of Code 2020](https://adventofcode.com/2020).
+//!
+//! Each day lives in its own `dayN` module with the same three entry points: `parse` turns the
+//! raw puzzle input into that day's input type (or a [`ParseError`] locating the bad text), and
+//! `part1` / `part2` solve each half of the puzzle from the parsed input.
+//!
+//! ```
+//! use advent_of_code_2020::day1;
+//!
+//! let input = day1::parse("1721\n979\n366\n299\n675\n1456").unwrap();
+//! assert_eq!(day1::part1(&input), 514579);
+//! assert_eq!(day1::part2(&input), 241861950);
+//! ```
+//!
+//! The same entry points are available at runtime through the [`solutions`] registry, which
+//! hides each day's input type behind the [`Solution`] trait:
+//!
+//! ```
+//! use advent_of_code_2020::solution;
+//!
+//! let day1 = solution(1).unwrap();
+//! let input = day1.parse("1721\n979\n366\n299\n675\n1456").unwrap();
+//! assert_eq!(day1.part1(&input).unwrap().to_string(), "514579");
+//! ```
+
 extern crate aoc_runner;
 extern crate bitvec;
 extern crate regex;
+extern crate serde_json;
 
 #[macro_use]
 extern crate aoc_runner_derive;
 
+pub mod bench;
 pub mod day1;
 pub mod day10;
 pub mod day11;
@@ -14,6 +41,7 @@ pub mod day14;
 pub mod day15;
 pub mod day16;
 pub mod day17;
+pub mod day18;
 pub mod day2;
 pub mod day3;
 pub mod day4;
@@ -22,6 +50,12 @@ pub mod day6;
 pub mod day7;
 pub mod day8;
 pub mod day9;
-pub mod day18;
+pub mod error;
+pub mod grid;
+pub mod solution;
+pub mod verify;
+
+pub use error::{ConfigError, ParseError, ParseErrorKind};
+pub use solution::{solution, solutions, Answer, Solution};
 
 aoc_lib! { year = 2020 }
@@ -1,7 +1,832 @@
 extern crate advent_of_code_2020;
-extern crate aoc_runner;
-extern crate aoc_runner_derive;
+extern crate serde_json;
 
-use aoc_runner_derive::aoc_main;
+use advent_of_code_2020::*;
+use serde_json::json;
+use std::error::Error;
+use std::io::{Read, Write};
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::{env, fs, io, process};
 
-aoc_main! { lib = advent_of_code_2020 }
+const USAGE: &str = "usage:
+    advent-of-code-2020 run --day <N> [--part <1|2>] [--input <path|->] [--format <text|json>]
+    advent-of-code-2020 run --all [--input-dir <dir>] [--format <text|json>]
+    advent-of-code-2020 bench (--day <N> | --all) [--runs <N>] [--baseline <path>] [--save <path>]
+                              [--threshold <percent>] [--format <text|json>]
+    advent-of-code-2020 verify [--input-dir <dir>] [--manifest <path>] [--format <text|json>]
+    advent-of-code-2020 slopes [--input <path|->] [--slopes <list>] [--format <text|json>]
+    advent-of-code-2020 slopes (--fewest | --most) [--dx <min..max>] [--dy <min..max>] [--input <path|->]
+    advent-of-code-2020 slopes --render <text|ppm|svg> [--scale <N>] [--slopes <dx,dy> | --fewest | --most]
+    advent-of-code-2020 passports [--input <path|->] [--schema <path>] [--report] [--format <text|json>]
+    advent-of-code-2020 list [--format <text|json>]
+
+options:
+    --day <N>          the day to run (1-18)
+    --part <1|2>       only run one part, both are run by default
+    --input <path|->   read the puzzle input from a file, or stdin with `-`
+                       (default: <input-dir>/day<N>.txt)
+    --input-dir <dir>  the directory holding day<N>.txt inputs (default: input)
+    --all              run every day found in the input directory
+    --manifest <path>  the expected answers to verify against
+                       (default: <input-dir>/answers.json)
+    --runs <N>         how many times to time each phase (default: 5)
+    --baseline <path>  a saved report to compare the timings against
+    --save <path>      write the timings to a report usable as a baseline
+    --threshold <pct>  how much slower than the baseline counts as a regression
+                       (default: 10)
+    --slopes <list>    day 3 slopes as `dx,dy` separated by spaces or `;`
+                       (default: the five part 2 slopes)
+    --fewest, --most   search for the day 3 slope hitting the fewest or most trees
+    --dx <min..max>    the horizontal steps to search, negative going left (default: -10..10)
+    --dy <min..max>    the vertical steps to search (default: 1..10)
+    --render <fmt>     draw one slope's path over the day 3 map as `text`, `ppm` or `svg`
+    --scale <N>        the size of each square in rendered images (default: 8)
+    --schema <path>    the day 4 passport rules, in TOML or in JSON if the path ends
+                       in `.json` (default: the puzzle's rules)
+    --report           list why each invalid passport was rejected and which rules
+                       fail most often
+    --format <fmt>     `text` (default) or `json`
+
+commands:
+    run                solve puzzles
+    bench              time parsing and solving each part, reporting regressions
+    slopes             count the trees hit on day 3 slopes, or search for the best slope
+    passports          count the day 4 passports that are complete and valid
+    verify             solve every day and compare with the recorded answers
+    list               show the implemented days and the missing parts";
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum Command {
+    Run,
+    Bench,
+    Slopes,
+    Passports,
+    Verify,
+    List,
+}
+
+impl Command {
+    fn name(self) -> &'static str {
+        match self {
+            Command::Run => "run",
+            Command::Bench => "bench",
+            Command::Slopes => "slopes",
+            Command::Passports => "passports",
+            Command::Verify => "verify",
+            Command::List => "list",
+        }
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum Render {
+    Text,
+    Ppm,
+    Svg,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum Search {
+    Fewest,
+    Most,
+}
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum Format {
+    Text,
+    Json,
+}
+
+struct Options {
+    command: Command,
+    days: Vec<u8>,
+    parts: Vec<u8>,
+    input: Option<String>,
+    input_dir: PathBuf,
+    manifest: Option<PathBuf>,
+    runs: usize,
+    baseline: Option<PathBuf>,
+    save: Option<PathBuf>,
+    threshold: f64,
+    slopes: Vec<day3::Slope>,
+    search: Option<Search>,
+    dx: RangeInclusive<isize>,
+    dy: RangeInclusive<usize>,
+    render: Option<Render>,
+    scale: usize,
+    schema: Option<PathBuf>,
+    report: bool,
+    format: Format,
+}
+
+struct Outcome {
+    day: u8,
+    part: u8,
+    answer: Result<String, String>,
+}
+
+fn solve(day: u8, parts: &[u8], input: &str) -> Result<Vec<(u8, String)>, Box<dyn Error>> {
+    let solution = solution(day).ok_or_else(|| format!("day {} is not implemented", day))?;
+    let parsed = solution.parse(input)?;
+    parts
+        .iter()
+        .map(|&part| match solution.part(part, &parsed) {
+            Some(answer) => Ok((part, answer.to_string())),
+            None => Err(format!("day {} part {} is not implemented", day, part).into()),
+        })
+        .collect()
+}
+
+fn list(format: Format) {
+    let solutions = solutions();
+    match format {
+        Format::Text => {
+            for s in &solutions {
+                let parts: Vec<String> = [1, 2]
+                    .iter()
+                    .filter(|&&p| s.has_part(p))
+                    .map(|p| p.to_string())
+                    .collect();
+                println!("day {}: {} (parts {})", s.day(), s.name(), parts.join(", "));
+            }
+            for (day, part) in solution::missing_parts() {
+                println!("day {} part {}: missing", day, part);
+            }
+        }
+        Format::Json => {
+            let days: Vec<_> = solutions
+                .iter()
+                .map(|s| {
+                    let parts: Vec<u8> =
+                        [1, 2].iter().copied().filter(|&p| s.has_part(p)).collect();
+                    json!({"day": s.day(), "name": s.name(), "parts": parts})
+                })
+                .collect();
+            let missing: Vec<_> = solution::missing_parts()
+                .into_iter()
+                .map(|(day, part)| json!({"day": day, "part": part}))
+                .collect();
+            println!("{}", json!({"days": days, "missing": missing}));
+        }
+    }
+}
+
+fn parse_range<T: FromStr>(v: &str) -> Result<RangeInclusive<T>, String> {
+    let mut bounds = v.splitn(2, "..");
+    match (
+        bounds.next().and_then(|b| b.parse().ok()),
+        bounds.next().and_then(|b| b.parse().ok()),
+    ) {
+        (Some(min), Some(max)) => Ok(min..=max),
+        _ => Err(format!("invalid range `{}`", v)),
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut args = args.iter();
+    let command = match args.next().map(String::as_str) {
+        Some("run") => Command::Run,
+        Some("bench") => Command::Bench,
+        Some("slopes") => Command::Slopes,
+        Some("passports") => Command::Passports,
+        Some("verify") => Command::Verify,
+        Some("list") => Command::List,
+        Some(other) => return Err(format!("unknown command `{}`", other)),
+        None => return Err(String::from("missing command")),
+    };
+    let mut day = None;
+    let mut all = false;
+    // options that only one command takes, with the command
+    let mut owned = vec![];
+    let mut options = Options {
+        command,
+        days: vec![],
+        parts: vec![1, 2],
+        input: None,
+        input_dir: PathBuf::from("input"),
+        manifest: None,
+        runs: 5,
+        baseline: None,
+        save: None,
+        threshold: 10.0,
+        slopes: day3::SLOPES.to_vec(),
+        search: None,
+        dx: -10..=10,
+        dy: 1..=10,
+        render: None,
+        scale: 8,
+        schema: None,
+        report: false,
+        format: Format::Text,
+    };
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("`{}` needs a value", arg))
+        };
+        match arg.as_str() {
+            "--day" => {
+                let v = value()?;
+                day = match v.parse() {
+                    Ok(d) if solution(d).is_some() => Some(d),
+                    _ => return Err(format!("invalid day `{}`", v)),
+                }
+            }
+            "--part" => {
+                options.parts = match value()?.as_str() {
+                    "1" => vec![1],
+                    "2" => vec![2],
+                    v => return Err(format!("invalid part `{}`", v)),
+                }
+            }
+            "--input" => options.input = Some(value()?.clone()),
+            "--input-dir" => options.input_dir = PathBuf::from(value()?),
+            "--manifest" => {
+                options.manifest = Some(PathBuf::from(value()?));
+                owned.push((Command::Verify, arg));
+            }
+            "--all" => all = true,
+            "--runs" => {
+                let v = value()?;
+                options.runs = match v.parse() {
+                    Ok(n) if n > 0 => n,
+                    _ => return Err(format!("invalid run count `{}`", v)),
+                };
+                owned.push((Command::Bench, arg));
+            }
+            "--baseline" => {
+                options.baseline = Some(PathBuf::from(value()?));
+                owned.push((Command::Bench, arg));
+            }
+            "--save" => {
+                options.save = Some(PathBuf::from(value()?));
+                owned.push((Command::Bench, arg));
+            }
+            "--threshold" => {
+                let v = value()?;
+                options.threshold = match v.parse() {
+                    Ok(t) if t >= 0.0 => t,
+                    _ => return Err(format!("invalid threshold `{}`", v)),
+                };
+                owned.push((Command::Bench, arg));
+            }
+            "--slopes" => {
+                options.slopes = day3::parse_slopes(value()?).map_err(|e| e.to_string())?;
+                owned.push((Command::Slopes, arg));
+            }
+            "--fewest" | "--most" => {
+                if options.search.is_some() {
+                    return Err(String::from("use either `--fewest` or `--most`"));
+                }
+                options.search = Some(if arg == "--fewest" {
+                    Search::Fewest
+                } else {
+                    Search::Most
+                });
+                owned.push((Command::Slopes, arg));
+            }
+            "--dx" => {
+                options.dx = parse_range(value()?)?;
+                owned.push((Command::Slopes, arg));
+            }
+            "--dy" => {
+                options.dy = parse_range(value()?)?;
+                owned.push((Command::Slopes, arg));
+            }
+            "--render" => {
+                options.render = match value()?.as_str() {
+                    "text" => Some(Render::Text),
+                    "ppm" => Some(Render::Ppm),
+                    "svg" => Some(Render::Svg),
+                    v => return Err(format!("invalid render format `{}`", v)),
+                };
+                owned.push((Command::Slopes, arg));
+            }
+            "--scale" => {
+                let v = value()?;
+                options.scale = match v.parse() {
+                    Ok(n) if n > 0 => n,
+                    _ => return Err(format!("invalid scale `{}`", v)),
+                };
+                owned.push((Command::Slopes, arg));
+            }
+            "--schema" => {
+                options.schema = Some(PathBuf::from(value()?));
+                owned.push((Command::Passports, arg));
+            }
+            "--report" => {
+                options.report = true;
+                owned.push((Command::Passports, arg));
+            }
+            "--format" => {
+                options.format = match value()?.as_str() {
+                    "text" => Format::Text,
+                    "json" => Format::Json,
+                    v => return Err(format!("invalid format `{}`", v)),
+                }
+            }
+            other => return Err(format!("unknown option `{}`", other)),
+        }
+    }
+    if let Some((owner, arg)) = owned.into_iter().find(|&(owner, _)| owner != command) {
+        return Err(format!("only `{}` takes `{}`", owner.name(), arg));
+    }
+    if command == Command::Slopes {
+        if day.is_some() || all {
+            return Err(String::from("`slopes` always reads day 3"));
+        }
+        if options.render.is_some() && options.search.is_none() && options.slopes.len() != 1 {
+            return Err(String::from("`--render` draws a single slope"));
+        }
+        options.days = vec![3];
+        return Ok(options);
+    }
+    if command == Command::Passports {
+        if day.is_some() || all {
+            return Err(String::from("`passports` always reads day 4"));
+        }
+        options.days = vec![4];
+        return Ok(options);
+    }
+    if command == Command::Verify || command == Command::List {
+        if day.is_some() || all || options.input.is_some() {
+            return Err(String::from(
+                "only `run` and `bench` take `--day`, `--all` or `--input`",
+            ));
+        }
+        return Ok(options);
+    }
+    options.days = match (day, all) {
+        (Some(d), false) => vec![d],
+        (None, true) if options.input.is_none() => solutions().iter().map(|s| s.day()).collect(),
+        (None, true) => {
+            return Err(String::from(
+                "`--all` reads from `--input-dir`, not `--input`",
+            ))
+        }
+        (Some(_), true) => return Err(String::from("use either `--day` or `--all`")),
+        (None, false) => return Err(String::from("missing `--day` or `--all`")),
+    };
+    Ok(options)
+}
+
+fn read_input(options: &Options, day: u8) -> io::Result<String> {
+    match options.input.as_deref() {
+        Some("-") => {
+            let mut input = String::new();
+            io::stdin().read_to_string(&mut input)?;
+            Ok(input)
+        }
+        Some(path) => fs::read_to_string(path),
+        None => fs::read_to_string(options.input_dir.join(format!("day{}.txt", day))),
+    }
+}
+
+fn run(options: &Options) -> Vec<Outcome> {
+    let mut outcomes = vec![];
+    for &day in &options.days {
+        let input = match read_input(options, day) {
+            Ok(input) => input,
+            // running everything skips the days we have no input for
+            Err(e) if options.days.len() > 1 && e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => {
+                outcomes.extend(options.parts.iter().map(|&part| Outcome {
+                    day,
+                    part,
+                    answer: Err(format!("could not read input: {}", e)),
+                }));
+                continue;
+            }
+        };
+        match solve(day, &options.parts, &input) {
+            Ok(answers) => outcomes.extend(answers.into_iter().map(|(part, answer)| Outcome {
+                day,
+                part,
+                answer: Ok(answer),
+            })),
+            Err(e) => outcomes.extend(options.parts.iter().map(|&part| Outcome {
+                day,
+                part,
+                answer: Err(e.to_string()),
+            })),
+        }
+    }
+    outcomes
+}
+
+fn benchmark(options: &Options) -> Result<Vec<bench::Comparison>, Box<dyn Error>> {
+    let mut measurements = vec![];
+    let mut failed = false;
+    for &day in &options.days {
+        let input = match read_input(options, day) {
+            Ok(input) => input,
+            Err(e) if options.days.len() > 1 && e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => {
+                eprintln!("day {}: error: could not read input: {}", day, e);
+                failed = true;
+                continue;
+            }
+        };
+        let solution = solution(day).unwrap();
+        match bench::bench_day(&*solution, &input, options.runs) {
+            Ok(m) => measurements.extend(m),
+            Err(e) => {
+                eprintln!("day {}: error: {}", day, e);
+                failed = true;
+            }
+        }
+    }
+    if failed {
+        return Err("some days could not be benchmarked".into());
+    }
+    if let Some(path) = &options.save {
+        fs::write(path, bench::to_json(&measurements).to_string())
+            .map_err(|e| format!("could not write {}: {}", path.display(), e))?;
+    }
+    let baseline = match &options.baseline {
+        Some(path) => bench::from_json(
+            &fs::read_to_string(path)
+                .map_err(|e| format!("could not read {}: {}", path.display(), e))?,
+        )?,
+        None => vec![],
+    };
+    Ok(bench::compare(&measurements, &baseline, options.threshold))
+}
+
+fn print_comparisons(comparisons: &[bench::Comparison], format: Format) {
+    match format {
+        Format::Text => println!("{}", bench::table(comparisons)),
+        Format::Json => {
+            let results: Vec<_> = comparisons
+                .iter()
+                .map(|c| {
+                    json!({
+                        "day": c.current.day,
+                        "phase": c.current.phase.to_string(),
+                        "nanos": c.current.median.as_nanos() as u64,
+                        "baseline_nanos": c.baseline.map(|b| b.as_nanos() as u64),
+                        "change_percent": c.change(),
+                        "regressed": c.regressed,
+                    })
+                })
+                .collect();
+            println!("{}", serde_json::Value::Array(results));
+        }
+    }
+}
+
+fn slopes(options: &Options) -> Result<(), Box<dyn Error>> {
+    let input = read_input(options, 3).map_err(|e| format!("could not read input: {}", e))?;
+    let map = day3::parse(&input)?;
+    let results = match options.search {
+        Some(search) => {
+            let (dx, dy) = (options.dx.clone(), options.dy.clone());
+            let best = match search {
+                Search::Fewest => map.fewest_trees(dx, dy),
+                Search::Most => map.most_trees(dx, dy),
+            };
+            vec![best.ok_or("no slopes within `--dx` and `--dy`")?]
+        }
+        None => options
+            .slopes
+            .iter()
+            .copied()
+            .zip(map.trees_on_slopes(&options.slopes))
+            .collect(),
+    };
+    if let Some(render) = options.render {
+        let (slope, _) = results[0];
+        let path = map.render(slope.dx, slope.dy);
+        let image = match render {
+            Render::Text => format!("{}\n", path.to_text()).into_bytes(),
+            Render::Ppm => path.to_ppm(options.scale),
+            Render::Svg => path.to_svg(options.scale).into_bytes(),
+        };
+        io::stdout().write_all(&image)?;
+        return Ok(());
+    }
+    match options.format {
+        Format::Text => {
+            for (slope, trees) in &results {
+                println!("slope {}: {} trees", slope, trees);
+            }
+        }
+        Format::Json => {
+            let results: Vec<_> = results
+                .iter()
+                .map(|(s, trees)| json!({"dx": s.dx, "dy": s.dy, "trees": trees}))
+                .collect();
+            println!("{}", serde_json::Value::Array(results));
+        }
+    }
+    Ok(())
+}
+
+fn passports(options: &Options) -> Result<(), Box<dyn Error>> {
+    let schema = match &options.schema {
+        Some(path) => {
+            let text = fs::read_to_string(path)
+                .map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+            match path.extension().and_then(|e| e.to_str()) {
+                Some("json") => day4::Schema::from_json(&text)?,
+                _ => day4::Schema::from_toml(&text)?,
+            }
+        }
+        None => day4::Schema::default(),
+    };
+    let input = read_input(options, 4).map_err(|e| format!("could not read input: {}", e))?;
+    let passports = day4::parse(&input)?;
+    let complete = passports.iter().filter(|p| schema.is_complete(p)).count();
+    let reports = day4::validate(&passports, &schema);
+    let invalid: Vec<_> = reports.iter().filter(|r| !r.is_valid()).collect();
+    let valid = reports.len() - invalid.len();
+    let failures = day4::histogram(&reports);
+    let warnings: Vec<&day4::Warning> = passports.iter().flat_map(|p| &p.warnings).collect();
+    match options.format {
+        Format::Text => {
+            println!("passports: {}", passports.len());
+            println!("complete: {}", complete);
+            println!("valid: {}", valid);
+            if options.report {
+                for r in &invalid {
+                    let violations: Vec<String> =
+                        r.violations.iter().map(|v| v.to_string()).collect();
+                    println!("line {}: {}", r.line, violations.join("; "));
+                }
+                println!("failures:");
+                for (rule, count) in &failures {
+                    println!("{:>8}  {}", count, rule);
+                }
+                for w in &warnings {
+                    eprintln!("warning: {}", w);
+                }
+            }
+        }
+        Format::Json => {
+            let mut result =
+                json!({"passports": passports.len(), "complete": complete, "valid": valid});
+            if options.report {
+                let invalid: Vec<_> = invalid
+                    .iter()
+                    .map(|r| {
+                        let violations: Vec<_> = r
+                            .violations
+                            .iter()
+                            .map(|v| {
+                                json!({
+                                    "field": v.field,
+                                    "value": v.value,
+                                    "failure": v.failure.name(),
+                                    "message": v.to_string(),
+                                })
+                            })
+                            .collect();
+                        json!({"line": r.line, "violations": violations})
+                    })
+                    .collect();
+                let failures: Vec<_> = failures
+                    .iter()
+                    .map(|(rule, count)| json!({"rule": rule, "count": count}))
+                    .collect();
+                let warnings: Vec<_> = warnings
+                    .iter()
+                    .map(|w| {
+                        let kind = match w.kind {
+                            day4::WarningKind::Duplicate => "duplicate",
+                            day4::WarningKind::Unknown => "unknown",
+                        };
+                        json!({
+                            "line": w.line,
+                            "column": w.column,
+                            "field": w.field,
+                            "kind": kind,
+                            "message": w.to_string(),
+                        })
+                    })
+                    .collect();
+                result["invalid"] = json!(invalid);
+                result["failures"] = json!(failures);
+                result["warnings"] = json!(warnings);
+            }
+            println!("{}", result);
+        }
+    }
+    Ok(())
+}
+
+fn verify(options: &Options) -> Result<Vec<verify::Check>, Box<dyn Error>> {
+    let path = match &options.manifest {
+        Some(path) => path.clone(),
+        None => options.input_dir.join("answers.json"),
+    };
+    let text = fs::read_to_string(&path)
+        .map_err(|e| format!("could not read {}: {}", path.display(), e))?;
+    let manifest = verify::Manifest::from_json(&text)?;
+    Ok(verify::verify(&manifest, |day| read_input(options, day)))
+}
+
+fn print_checks(checks: &[verify::Check], format: Format) {
+    match format {
+        Format::Text => println!("{}", verify::table(checks)),
+        Format::Json => {
+            let results: Vec<_> = checks
+                .iter()
+                .map(|c| {
+                    let (status, detail) = match &c.status {
+                        verify::Status::Pass => ("pass", json!({})),
+                        verify::Status::Fail { expected, actual } => {
+                            ("fail", json!({"expected": expected, "actual": actual}))
+                        }
+                        verify::Status::Error(e) => ("error", json!({ "error": e })),
+                        verify::Status::Unrecorded(actual) => {
+                            ("unrecorded", json!({ "actual": actual }))
+                        }
+                    };
+                    let mut result = json!({"day": c.day, "part": c.part, "status": status});
+                    result
+                        .as_object_mut()
+                        .unwrap()
+                        .extend(detail.as_object().unwrap().clone());
+                    result
+                })
+                .collect();
+            println!("{}", serde_json::Value::Array(results));
+        }
+    }
+}
+
+fn print(outcomes: &[Outcome], format: Format) {
+    match format {
+        Format::Text => {
+            for o in outcomes {
+                match &o.answer {
+                    Ok(answer) => println!("day {} part {}: {}", o.day, o.part, answer),
+                    Err(e) => eprintln!("day {} part {}: error: {}", o.day, o.part, e),
+                }
+            }
+        }
+        Format::Json => {
+            let results: Vec<_> = outcomes
+                .iter()
+                .map(|o| match &o.answer {
+                    Ok(answer) => json!({"day": o.day, "part": o.part, "answer": answer}),
+                    Err(e) => json!({"day": o.day, "part": o.part, "error": e}),
+                })
+                .collect();
+            println!("{}", serde_json::Value::Array(results));
+        }
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.iter().any(|a| a == "--help" || a == "-h") {
+        println!("{}", USAGE);
+        return;
+    }
+    let options = match parse_args(&args) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
+    match options.command {
+        Command::Run => {}
+        Command::List => return list(options.format),
+        Command::Slopes => {
+            if let Err(e) = slopes(&options) {
+                eprintln!("error: {}", e);
+                process::exit(1);
+            }
+            return;
+        }
+        Command::Passports => {
+            if let Err(e) = passports(&options) {
+                eprintln!("error: {}", e);
+                process::exit(1);
+            }
+            return;
+        }
+        Command::Bench => match benchmark(&options) {
+            Ok(comparisons) => {
+                print_comparisons(&comparisons, options.format);
+                if comparisons.iter().any(|c| c.regressed) {
+                    process::exit(1);
+                }
+                return;
+            }
+            Err(e) => {
+                eprintln!("error: {}", e);
+                process::exit(1);
+            }
+        },
+        Command::Verify => match verify(&options) {
+            Ok(checks) => {
+                print_checks(&checks, options.format);
+                if !checks.iter().all(verify::Check::is_ok) {
+                    process::exit(1);
+                }
+                return;
+            }
+            Err(e) => {
+                eprintln!("error: {}", e);
+                process::exit(1);
+            }
+        },
+    }
+    let outcomes = run(&options);
+    print(&outcomes, options.format);
+    if outcomes.is_empty() {
+        eprintln!("error: no inputs found in {}", options.input_dir.display());
+        process::exit(1);
+    }
+    if outcomes.iter().any(|o| o.answer.is_err()) {
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split(' ').map(String::from).collect()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let options = parse_args(&args("run --day 7 --part 2 --input - --format json")).unwrap();
+        assert_eq!(options.days, vec![7]);
+        assert_eq!(options.parts, vec![2]);
+        assert_eq!(options.input.as_deref(), Some("-"));
+        assert!(options.format == Format::Json);
+
+        let options = parse_args(&args("run --all --input-dir inputs")).unwrap();
+        assert_eq!(options.days.len(), 18);
+        assert_eq!(options.parts, vec![1, 2]);
+        assert_eq!(options.input_dir, PathBuf::from("inputs"));
+
+        assert!(parse_args(&args("run --day 19")).is_err());
+        assert!(parse_args(&args("run --all --input day1.txt")).is_err());
+        assert!(parse_args(&args("run --part 1")).is_err());
+        assert!(parse_args(&args("solve --day 1")).is_err());
+
+        let options = parse_args(&args("list --format json")).unwrap();
+        assert!(options.command == Command::List);
+        assert!(parse_args(&args("list --day 1")).is_err());
+
+        let options = parse_args(&args("verify --manifest answers.json")).unwrap();
+        assert!(options.command == Command::Verify);
+        assert_eq!(options.manifest, Some(PathBuf::from("answers.json")));
+        assert!(parse_args(&args("run --day 1 --manifest answers.json")).is_err());
+
+        let options = parse_args(&args(
+            "bench --all --runs 3 --baseline b.json --threshold 5",
+        ))
+        .unwrap();
+        assert!(options.command == Command::Bench);
+        assert_eq!(options.days.len(), 18);
+        assert_eq!(options.runs, 3);
+        assert_eq!(options.baseline, Some(PathBuf::from("b.json")));
+        assert!((options.threshold - 5.0).abs() < f64::EPSILON);
+        assert!(parse_args(&args("bench --day 1 --runs 0")).is_err());
+        assert!(parse_args(&args("run --day 1 --save b.json")).is_err());
+
+        let options = parse_args(&args("slopes --slopes 3,1;-1,2")).unwrap();
+        assert_eq!(options.days, vec![3]);
+        assert_eq!(options.slopes.len(), 2);
+        assert_eq!(options.slopes[1], day3::Slope { dx: -1, dy: 2 });
+        let options = parse_args(&args("slopes --most --dx -3..3 --dy 1..2")).unwrap();
+        assert!(options.search == Some(Search::Most));
+        assert_eq!((options.dx, options.dy), (-3..=3, 1..=2));
+        assert!(parse_args(&args("slopes --fewest --most")).is_err());
+        assert!(parse_args(&args("slopes --dx 1")).is_err());
+        assert!(parse_args(&args("slopes --day 3")).is_err());
+        assert!(parse_args(&args("run --day 3 --most")).is_err());
+        assert!(parse_args(&args("slopes --render svg --slopes 1,1 --scale 4")).is_ok());
+        assert!(parse_args(&args("slopes --render ppm --most")).is_ok());
+        assert!(parse_args(&args("slopes --render text")).is_err());
+        assert!(parse_args(&args("slopes --render gif --slopes 1,1")).is_err());
+
+        let options = parse_args(&args("passports --schema rules.toml --input -")).unwrap();
+        assert_eq!(options.days, vec![4]);
+        assert_eq!(options.schema, Some(PathBuf::from("rules.toml")));
+        assert!(
+            parse_args(&args("passports --report --format json"))
+                .unwrap()
+                .report
+        );
+        assert!(parse_args(&args("passports --day 4")).is_err());
+        assert!(parse_args(&args("run --day 4 --schema rules.toml")).is_err());
+    }
+
+    #[test]
+    fn test_solve() {
+        let answers = solve(1, &[2], "1721\n979\n366\n299\n675\n1456").unwrap();
+        assert_eq!(answers, vec![(2, String::from("241861950"))]);
+        assert!(solve(1, &[1], "1721\nabc").is_err());
+    }
+}
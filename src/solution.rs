@@ -0,0 +1,223 @@
+use crate::error::ParseError;
+use crate::*;
+use std::any::Any;
+use std::fmt;
+
+/// The days of the calendar, implemented or not.
+pub const CALENDAR: std::ops::RangeInclusive<u8> = 1..=25;
+
+/// The answer to one part of a puzzle.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Answer {
+    Unsigned(u128),
+    Signed(i128),
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Answer::Unsigned(n) => write!(f, "{}", n),
+            Answer::Signed(n) => write!(f, "{}", n),
+        }
+    }
+}
+
+impl From<usize> for Answer {
+    fn from(n: usize) -> Self {
+        Answer::Unsigned(n as u128)
+    }
+}
+
+impl From<u128> for Answer {
+    fn from(n: u128) -> Self {
+        Answer::Unsigned(n)
+    }
+}
+
+impl From<i32> for Answer {
+    fn from(n: i32) -> Self {
+        Answer::Signed(n as i128)
+    }
+}
+
+/// A parsed puzzle input, only usable with the solution that produced it.
+pub struct Parsed(Box<dyn Any>);
+
+/// One day of the calendar, with its input type hidden so every day can share a registry.
+pub trait Solution: Sync {
+    /// The day of December the puzzle was released.
+    fn day(&self) -> u8;
+
+    /// The puzzle's title.
+    fn name(&self) -> &'static str;
+
+    /// Parses the raw puzzle input.
+    fn parse(&self, input: &str) -> Result<Parsed, ParseError>;
+
+    /// Solves part 1, or `None` if it is not implemented.
+    fn part1(&self, input: &Parsed) -> Option<Answer>;
+
+    /// Solves part 2, or `None` if it is not implemented.
+    fn part2(&self, input: &Parsed) -> Option<Answer>;
+
+    /// Whether `part` (1 or 2) is implemented.
+    fn has_part(&self, part: u8) -> bool;
+
+    /// Solves `part` (1 or 2), or `None` if it is not implemented.
+    fn part(&self, part: u8, input: &Parsed) -> Option<Answer> {
+        match part {
+            1 => self.part1(input),
+            2 => self.part2(input),
+            _ => None,
+        }
+    }
+}
+
+struct Day<T> {
+    day: u8,
+    name: &'static str,
+    parse: fn(&str) -> Result<T, ParseError>,
+    part1: Option<fn(&T) -> Answer>,
+    part2: Option<fn(&T) -> Answer>,
+}
+
+impl<T: 'static> Day<T> {
+    fn solve(&self, part: Option<fn(&T) -> Answer>, input: &Parsed) -> Option<Answer> {
+        let input = input
+            .0
+            .downcast_ref::<T>()
+            .unwrap_or_else(|| panic!("input was not parsed by day {}", self.day));
+        part.map(|f| f(input))
+    }
+}
+
+impl<T: 'static> Solution for Day<T> {
+    fn day(&self) -> u8 {
+        self.day
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn parse(&self, input: &str) -> Result<Parsed, ParseError> {
+        Ok(Parsed(Box::new((self.parse)(input)?)))
+    }
+
+    fn part1(&self, input: &Parsed) -> Option<Answer> {
+        self.solve(self.part1, input)
+    }
+
+    fn part2(&self, input: &Parsed) -> Option<Answer> {
+        self.solve(self.part2, input)
+    }
+
+    fn has_part(&self, part: u8) -> bool {
+        match part {
+            1 => self.part1.is_some(),
+            2 => self.part2.is_some(),
+            _ => false,
+        }
+    }
+}
+
+macro_rules! day {
+    ($module:ident, $day:literal, $name:literal, $($part:ident),*) => {
+        Box::new(Day {
+            day: $day,
+            name: $name,
+            parse: $module::parse,
+            part1: day!(@part $module, part1, $($part),*),
+            part2: day!(@part $module, part2, $($part),*),
+        })
+    };
+    (@part $module:ident, part1, part1 $(, $rest:ident)*) => {
+        Some(|input| $module::part1(input).into())
+    };
+    (@part $module:ident, part2, $(part1,)? part2) => {
+        Some(|input| $module::part2(input).into())
+    };
+    (@part $module:ident, $part:ident, $($parts:ident),*) => {
+        None
+    };
+}
+
+/// Every implemented day, in calendar order.
+pub fn solutions() -> Vec<Box<dyn Solution>> {
+    vec![
+        day!(day1, 1, "Report Repair", part1, part2),
+        day!(day2, 2, "Password Philosophy", part1, part2),
+        day!(day3, 3, "Toboggan Trajectory", part1, part2),
+        day!(day4, 4, "Passport Processing", part1, part2),
+        day!(day5, 5, "Binary Boarding", part1, part2),
+        day!(day6, 6, "Custom Customs", part1, part2),
+        day!(day7, 7, "Handy Haversacks", part1, part2),
+        day!(day8, 8, "Handheld Halting", part1, part2),
+        day!(day9, 9, "Encoding Error", part1, part2),
+        day!(day10, 10, "Adapter Array", part1, part2),
+        day!(day11, 11, "Seating System", part1, part2),
+        day!(day12, 12, "Rain Risk", part1, part2),
+        day!(day13, 13, "Shuttle Search", part1, part2),
+        day!(day14, 14, "Docking Data", part1, part2),
+        day!(day15, 15, "Rambunctious Recitation", part1, part2),
+        day!(day16, 16, "Ticket Translation", part1, part2),
+        day!(day17, 17, "Conway Cubes", part1, part2),
+        day!(day18, 18, "Operation Order", part1, part2),
+    ]
+}
+
+/// The solution for `day`, if it is implemented.
+pub fn solution(day: u8) -> Option<Box<dyn Solution>> {
+    solutions().into_iter().find(|s| s.day() == day)
+}
+
+/// Every `(day, part)` of the calendar that has no implementation.
+pub fn missing_parts() -> Vec<(u8, u8)> {
+    let solutions = solutions();
+    CALENDAR
+        .flat_map(|day| [1, 2].iter().map(move |&part| (day, part)))
+        .filter(|&(day, part)| !solutions.iter().any(|s| s.day() == day && s.has_part(part)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry() {
+        let days: Vec<u8> = solutions().iter().map(|s| s.day()).collect();
+        assert_eq!(days, (1..=18).collect::<Vec<u8>>());
+        let missing = missing_parts();
+        assert_eq!(missing.len(), 14);
+        assert!(missing.iter().all(|&(day, _)| day > 18));
+    }
+
+    #[test]
+    fn test_solve_through_registry() {
+        let day = solution(8).unwrap();
+        assert_eq!(day.name(), "Handheld Halting");
+        let input = day
+            .parse("nop +0\nacc +1\njmp +4\nacc +3\njmp -3\nacc -99\nacc +1\njmp -4\nacc +6")
+            .unwrap();
+        assert_eq!(day.part1(&input), Some(Answer::Signed(5)));
+        assert_eq!(day.part(2, &input), Some(Answer::Signed(8)));
+        assert_eq!(day.part(3, &input), None);
+        assert!(day.parse("nop +0\nbad").is_err());
+    }
+
+    #[test]
+    fn test_missing_part() {
+        let day: Day<Vec<usize>> = Day {
+            day: 1,
+            name: "Report Repair",
+            parse: day1::parse,
+            part1: Some(|input| day1::part1(input).into()),
+            part2: None,
+        };
+        let input = day.parse("1721\n299").unwrap();
+        assert_eq!(day.part1(&input), Some(Answer::Unsigned(514579)));
+        assert_eq!(day.part2(&input), None);
+        assert!(day.has_part(1) && !day.has_part(2));
+    }
+}
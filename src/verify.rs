@@ -0,0 +1,219 @@
+use crate::error::ConfigError;
+use crate::solution::solutions;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::io;
+
+fn invalid(message: String) -> ConfigError {
+    ConfigError::new("manifest", message)
+}
+
+/// The recorded answers for each `(day, part)`.
+///
+/// Manifests are JSON objects keyed by day, then by part, with answers as numbers or strings:
+///
+/// ```json
+/// { "day1": { "part1": 514579, "part2": "241861950" } }
+/// ```
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Manifest {
+    pub answers: BTreeMap<(u8, u8), String>,
+}
+
+fn key(prefix: &str, key: &str) -> Option<u8> {
+    key.strip_prefix(prefix).and_then(|n| n.parse().ok())
+}
+
+impl Manifest {
+    /// Reads a manifest from its JSON text.
+    pub fn from_json(text: &str) -> Result<Manifest, ConfigError> {
+        let value: Value = serde_json::from_str(text).map_err(|e| invalid(e.to_string()))?;
+        let days = value
+            .as_object()
+            .ok_or_else(|| invalid(String::from("expected an object of days")))?;
+        let mut answers = BTreeMap::new();
+        for (day_key, parts) in days {
+            let day = key("day", day_key)
+                .ok_or_else(|| invalid(format!("`{}` is not a day like `day1`", day_key)))?;
+            let parts = parts
+                .as_object()
+                .ok_or_else(|| invalid(format!("`{}` should be an object of parts", day_key)))?;
+            for (part_key, answer) in parts {
+                let part = key("part", part_key)
+                    .filter(|p| *p == 1 || *p == 2)
+                    .ok_or_else(|| {
+                        invalid(format!(
+                            "`{}.{}` is not `part1` or `part2`",
+                            day_key, part_key
+                        ))
+                    })?;
+                let answer = match answer {
+                    Value::String(s) => s.clone(),
+                    Value::Number(n) => n.to_string(),
+                    _ => {
+                        return Err(invalid(format!(
+                            "`{}.{}` should be a number or string",
+                            day_key, part_key
+                        )))
+                    }
+                };
+                answers.insert((day, part), answer);
+            }
+        }
+        Ok(Manifest { answers })
+    }
+
+    /// The recorded answer for `day` and `part`.
+    pub fn expected(&self, day: u8, part: u8) -> Option<&str> {
+        self.answers.get(&(day, part)).map(String::as_str)
+    }
+}
+
+/// How one part compared with the manifest.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum Status {
+    /// The answer matched the manifest.
+    Pass,
+    /// The answer differed from the manifest.
+    Fail { expected: String, actual: String },
+    /// The part could not be solved, e.g. its input is unreadable or malformed.
+    Error(String),
+    /// The part was solved but the manifest has no answer for it.
+    Unrecorded(String),
+}
+
+/// The result of verifying one part.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Check {
+    pub day: u8,
+    pub part: u8,
+    pub status: Status,
+}
+
+impl Check {
+    /// Whether the part passed or had nothing to compare against.
+    pub fn is_ok(&self) -> bool {
+        matches!(self.status, Status::Pass | Status::Unrecorded(_))
+    }
+}
+
+/// Solves every implemented part and compares it with `manifest`, reading each day's input
+/// with `input`. Days with no input and no recorded answers are skipped.
+pub fn verify<F>(manifest: &Manifest, input: F) -> Vec<Check>
+where
+    F: Fn(u8) -> io::Result<String>,
+{
+    let mut checks = vec![];
+    for solution in solutions() {
+        let day = solution.day();
+        let parts: Vec<u8> = [1, 2]
+            .iter()
+            .copied()
+            .filter(|&p| solution.has_part(p))
+            .collect();
+        let recorded = parts.iter().any(|&p| manifest.expected(day, p).is_some());
+        let parsed = match input(day) {
+            Ok(text) => solution.parse(&text).map_err(|e| e.to_string()),
+            Err(e) if !recorded && e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => Err(format!("could not read input: {}", e)),
+        };
+        for part in parts {
+            let status = match &parsed {
+                Ok(parsed) => {
+                    let actual = solution.part(part, parsed).unwrap().to_string();
+                    match manifest.expected(day, part) {
+                        Some(expected) if expected == actual => Status::Pass,
+                        Some(expected) => Status::Fail {
+                            expected: String::from(expected),
+                            actual,
+                        },
+                        None => Status::Unrecorded(actual),
+                    }
+                }
+                Err(e) => Status::Error(e.clone()),
+            };
+            checks.push(Check { day, part, status });
+        }
+    }
+    checks
+}
+
+/// Renders `checks` as a pass/fail table with the expected and actual answers of failures.
+pub fn table(checks: &[Check]) -> String {
+    let mut out = String::from("day part status      detail\n");
+    for c in checks {
+        let (status, detail) = match &c.status {
+            Status::Pass => ("pass", String::new()),
+            Status::Fail { expected, actual } => (
+                "FAIL",
+                format!("expected {}\n{:21}actual   {}", expected, "", actual),
+            ),
+            Status::Error(e) => ("ERROR", e.clone()),
+            Status::Unrecorded(actual) => ("unrecorded", format!("actual {}", actual)),
+        };
+        out.push_str(&format!(
+            "{:>3} {:>4} {:<11} {}",
+            c.day, c.part, status, detail
+        ));
+        out.truncate(out.trim_end_matches(' ').len());
+        out.push('\n');
+    }
+    let passed = checks.iter().filter(|c| c.status == Status::Pass).count();
+    let failed = checks.iter().filter(|c| !c.is_ok()).count();
+    out.push_str(&format!("{} passed, {} failed", passed, failed));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY1: &str = "1721\n979\n366\n299\n675\n1456";
+
+    fn input(day: u8) -> io::Result<String> {
+        match day {
+            1 => Ok(String::from(DAY1)),
+            2 => Ok(String::from("1-3 a abcde\n1-3 b")),
+            _ => Err(io::Error::from(io::ErrorKind::NotFound)),
+        }
+    }
+
+    #[test]
+    fn test_manifest() {
+        let manifest = Manifest::from_json(r#"{"day1": {"part1": 514579, "part2": "1"}}"#).unwrap();
+        assert_eq!(manifest.expected(1, 1), Some("514579"));
+        assert_eq!(manifest.expected(1, 2), Some("1"));
+        assert_eq!(manifest.expected(2, 1), None);
+        assert!(Manifest::from_json(r#"{"one": {}}"#).is_err());
+        assert!(Manifest::from_json(r#"{"day1": {"part3": 1}}"#).is_err());
+        assert!(Manifest::from_json(r#"{"day1": {"part1": true}}"#).is_err());
+    }
+
+    #[test]
+    fn test_verify() {
+        let manifest =
+            Manifest::from_json(r#"{"day1": {"part1": 514579, "part2": 1}, "day3": {"part1": 7}}"#)
+                .unwrap();
+        let checks = verify(&manifest, input);
+        let statuses: Vec<_> = checks.iter().map(|c| (c.day, c.part, &c.status)).collect();
+        assert_eq!(statuses.len(), 6);
+        assert_eq!(statuses[0], (1, 1, &Status::Pass));
+        assert_eq!(
+            statuses[1],
+            (
+                1,
+                2,
+                &Status::Fail {
+                    expected: String::from("1"),
+                    actual: String::from("241861950"),
+                }
+            )
+        );
+        assert!(matches!(statuses[2], (2, 1, Status::Error(_))));
+        assert!(matches!(statuses[4], (3, 1, Status::Error(_))));
+
+        let table = table(&checks);
+        assert!(table.contains("  1    2 FAIL        expected 1\n"));
+        assert!(table.ends_with("1 passed, 5 failed"));
+    }
+}